@@ -0,0 +1,288 @@
+use crate::connection::Connection;
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::metrics::{self, Operation};
+use crate::wait;
+use nostr_types::{ClientMessage, EventKind, Filter, RelayMessage, Tag};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+use tokio::sync::Barrier;
+
+pub const DEFAULT_CONNECTIONS: usize = 4;
+pub const DEFAULT_EVENTS_PER_SECOND: f64 = 100.0;
+pub const DEFAULT_SECONDS: u64 = 10;
+
+// Every benchmark event carries this tag so queries only find those
+const BENCH_TAG: &str = "relay-tester-bench";
+
+#[derive(Debug, Default)]
+struct Tally {
+    published: AtomicUsize,
+    accepted: AtomicUsize,
+    rejected: AtomicUsize,
+    rate_limited: AtomicUsize,
+    errors: AtomicUsize,
+    queries: AtomicUsize,
+    eoses: AtomicUsize,
+    queries_closed: AtomicUsize,
+    queries_rate_limited: AtomicUsize,
+    query_errors: AtomicUsize,
+}
+
+impl Tally {
+    fn bump(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn get(counter: &AtomicUsize) -> usize {
+        counter.load(Ordering::Relaxed)
+    }
+}
+
+/// Publish events as the registered user at a target total rate, spread
+/// over this many connections, while as many other connections query them
+/// back as fast as the relay answers. Then print throughput, latency and
+/// error counts.
+pub async fn run(connections: usize, events_per_second: f64, seconds: u64) -> Result<(), Error> {
+    tracing::info!(
+        "Benchmarking with {} connections, {} events/s, for {}s",
+        connections,
+        events_per_second,
+        seconds
+    );
+
+    // The connection Globals::init() opened is not used
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .disconnect()
+        .await?;
+
+    let relay_url = GLOBALS.relay_url.read().clone();
+    let tally = Arc::new(Tally::default());
+    let duration = Duration::from_secs(seconds);
+    let per_connection = events_per_second / connections as f64;
+
+    // Everybody connects and authenticates before the clock starts
+    let start = Arc::new(Barrier::new(connections * 2 + 1));
+
+    let mut tasks = Vec::new();
+    for i in 0..connections {
+        tasks.push(tokio::spawn(publish(
+            format!("publisher{}", i),
+            relay_url.clone(),
+            per_connection,
+            duration,
+            start.clone(),
+            tally.clone(),
+        )));
+        tasks.push(tokio::spawn(query(
+            format!("querier{}", i),
+            relay_url.clone(),
+            duration,
+            start.clone(),
+            tally.clone(),
+        )));
+    }
+
+    start.wait().await;
+    let started = Instant::now();
+    for task in tasks {
+        task.await?;
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let per_second = |n: usize| n as f64 / elapsed;
+    let accepted = Tally::get(&tally.accepted);
+    let eoses = Tally::get(&tally.eoses);
+
+    println!("BENCHMARK RESULTS\n");
+    println!("Relay: {}", relay_url);
+    println!(
+        "Connections: {} publishing, {} querying, for {:.1}s\n",
+        connections, connections, elapsed
+    );
+    println!(
+        "published: {} ({:.1}/s, target {:.1}/s)",
+        Tally::get(&tally.published),
+        per_second(Tally::get(&tally.published)),
+        events_per_second
+    );
+    println!("accepted: {} ({:.1}/s)", accepted, per_second(accepted));
+    println!("rejected: {}", Tally::get(&tally.rejected));
+    println!("rate-limited: {}", Tally::get(&tally.rate_limited));
+    println!("errors: {}", Tally::get(&tally.errors));
+    println!(
+        "queries: {}, answered with EOSE: {} ({:.1}/s)",
+        Tally::get(&tally.queries),
+        eoses,
+        per_second(eoses)
+    );
+    println!("queries closed: {}", Tally::get(&tally.queries_closed));
+    println!(
+        "queries rate-limited: {}",
+        Tally::get(&tally.queries_rate_limited)
+    );
+    println!("query errors: {}\n", Tally::get(&tally.query_errors));
+
+    let latencies = GLOBALS.metrics.read();
+    for operation in Operation::iter() {
+        println!(
+            "{}: {}",
+            operation.name(),
+            metrics::summarize(latencies.samples(operation))
+        );
+    }
+
+    Ok(())
+}
+
+// Errors end the task rather than the benchmark; they are counted instead
+async fn publish(
+    name: String,
+    relay_url: String,
+    per_second: f64,
+    duration: Duration,
+    start: Arc<Barrier>,
+    tally: Arc<Tally>,
+) {
+    let connection = connect(name, relay_url).await;
+    start.wait().await;
+    let mut connection = match connection {
+        Some(c) => c,
+        None => return Tally::bump(&tally.errors),
+    };
+
+    let until = Instant::now() + duration;
+    // At very high rates the period rounds down to nothing, which interval()
+    // does not accept; at very low ones it overflows, so one event is all
+    // this connection gets to publish
+    let period = Duration::try_from_secs_f64(1.0 / per_second)
+        .unwrap_or(duration)
+        .max(Duration::from_nanos(1));
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    while Instant::now() < until {
+        interval.tick().await;
+
+        let content = format!("benchmark {}", rand::random::<u64>());
+        let event = match Globals::make_event(
+            EventParts::Basic(
+                EventKind::TextNote,
+                vec![Tag::new(&["t", BENCH_TAG])],
+                content,
+            ),
+            true,
+        ) {
+            Ok(event) => event,
+            Err(_) => return Tally::bump(&tally.errors),
+        };
+
+        Tally::bump(&tally.published);
+        match connection.post_event(event, wait()).await {
+            Ok((true, _)) => Tally::bump(&tally.accepted),
+            Ok((false, reason)) if reason.starts_with("rate-limited:") => {
+                Tally::bump(&tally.rate_limited)
+            }
+            Ok((false, _)) => Tally::bump(&tally.rejected),
+            Err(e) if lost(&e) => {
+                Tally::bump(&tally.errors);
+                break;
+            }
+            Err(_) => Tally::bump(&tally.errors),
+        }
+    }
+
+    let _ = connection.disconnect().await;
+}
+
+async fn query(
+    name: String,
+    relay_url: String,
+    duration: Duration,
+    start: Arc<Barrier>,
+    tally: Arc<Tally>,
+) {
+    let connection = connect(name, relay_url).await;
+    start.wait().await;
+    let mut connection = match connection {
+        Some(c) => c,
+        None => return Tally::bump(&tally.query_errors),
+    };
+
+    let filter = {
+        let mut filter = Filter::new();
+        filter.kinds = vec![EventKind::TextNote];
+        filter.add_tag_value('t', BENCH_TAG.to_owned());
+        filter.limit = Some(100);
+        filter
+    };
+
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        Tally::bump(&tally.queries);
+        match query_once(&mut connection, filter.clone()).await {
+            Ok(None) => Tally::bump(&tally.eoses),
+            Ok(Some(reason)) if reason.starts_with("rate-limited:") => {
+                Tally::bump(&tally.queries_rate_limited)
+            }
+            Ok(Some(_)) => Tally::bump(&tally.queries_closed),
+            Err(e) if lost(&e) => {
+                Tally::bump(&tally.query_errors);
+                break;
+            }
+            Err(_) => Tally::bump(&tally.query_errors),
+        }
+    }
+
+    let _ = connection.disconnect().await;
+}
+
+// Whether the connection is gone, so that retrying right away would only
+// pile up errors as fast as the loop can spin
+fn lost(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Disconnected | Error::Websocket(_) | Error::Io(_)
+    )
+}
+
+// Unlike Connection::fetch_events(), this returns as soon as the EOSE comes
+// rather than waiting for more events. Returns the CLOSED message if the
+// relay closed the subscription instead.
+async fn query_once(connection: &mut Connection, filter: Filter) -> Result<Option<String>, Error> {
+    let sub_id = connection.next_subscription_id();
+    let sent = Instant::now();
+    connection
+        .send_message(ClientMessage::Req(sub_id.clone(), vec![filter]))
+        .await?;
+
+    loop {
+        match connection.wait_for_message(wait()).await? {
+            None => return Err(Error::TimedOut),
+            Some(RelayMessage::Eose(sub)) if sub == sub_id => {
+                GLOBALS
+                    .metrics
+                    .write()
+                    .record(Operation::TimeToEose, sent.elapsed());
+                connection.close_subscription(sub_id).await?;
+                return Ok(None);
+            }
+            Some(RelayMessage::Closed(sub, msg)) if sub == sub_id => return Ok(Some(msg)),
+            Some(_) => continue,
+        }
+    }
+}
+
+// Connect and authenticate as the registered user if challenged
+async fn connect(name: String, relay_url: String) -> Option<Connection> {
+    let mut connection = Connection::new(relay_url).await.ok()?;
+    connection.name = name;
+    connection.authenticate(true, wait()).await.ok()?;
+    Some(connection)
+}
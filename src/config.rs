@@ -0,0 +1,153 @@
+use crate::error::Error;
+use crate::profile::Profile;
+use crate::stage::Stage;
+use crate::test_item::TestItem;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+/// A relay-tester run described in a JSON file, so that a test matrix can be
+/// kept in version control next to the relay deployment it tests.
+///
+/// Stage and test names are the Rust identifiers (e.g. "Registered",
+/// "PublicCanWrite").
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub relays: Vec<RelayConfig>,
+    pub timeouts: Timeouts,
+
+    // Stages to run. All of them if absent.
+    pub stages: Option<Vec<String>>,
+
+    // Overrides of TestItem::required()
+    pub required: BTreeMap<String, bool>,
+
+    // Profile for relays that do not declare their own
+    pub profile: Option<String>,
+
+    // Scenario files to run against every relay before the suite
+    pub scenarios: Vec<String>,
+
+    // Run the tests that flood the relay (TestItem::floods())
+    pub flood: bool,
+
+    // Run the tests that make the registered key vanish
+    // (TestItem::destructive())
+    pub vanish: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RelayConfig {
+    pub url: String,
+    pub nsec: String,
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+/// Timeouts, in seconds
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    // How long to wait for a relay to reply before deciding it will not
+    pub wait: u64,
+
+    // How long to wait before reconnecting after a disconnect
+    pub reconnect: u64,
+
+    // How long to wait for the websocket to connect
+    pub connect: u64,
+}
+
+impl Default for Timeouts {
+    fn default() -> Timeouts {
+        Timeouts {
+            wait: 2,
+            reconnect: 3,
+            connect: 5,
+        }
+    }
+}
+
+impl Timeouts {
+    pub fn wait(&self) -> Duration {
+        Duration::from_secs(self.wait)
+    }
+
+    pub fn reconnect(&self) -> Duration {
+        Duration::from_secs(self.reconnect)
+    }
+
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect)
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Config, Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let config: Config = serde_json::from_str(&contents)?;
+
+        // Check names now rather than failing part way through a run
+        if config.relays.is_empty() {
+            return Err(Error::Config("No relays configured".to_owned()));
+        }
+        for relay in config.relays.iter() {
+            if let Some(name) = &relay.profile {
+                Self::parse_profile(name)?;
+            }
+        }
+        if let Some(name) = &config.profile {
+            Self::parse_profile(name)?;
+        }
+        config.enabled_stages()?;
+        config.required_overrides()?;
+        for path in config.scenarios.iter() {
+            crate::scenario::load(path)?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn enabled_stages(&self) -> Result<Vec<Stage>, Error> {
+        let names = match &self.stages {
+            Some(names) => names,
+            None => return Ok(Stage::iter().collect()),
+        };
+        let mut stages: Vec<Stage> = Vec::new();
+        for name in names.iter() {
+            match Stage::iter().find(|s| format!("{:?}", s) == *name) {
+                Some(stage) => stages.push(stage),
+                None => return Err(Error::Config(format!("Unknown stage {}", name))),
+            }
+        }
+        Ok(stages)
+    }
+
+    pub fn required_overrides(&self) -> Result<BTreeMap<TestItem, bool>, Error> {
+        let mut overrides: BTreeMap<TestItem, bool> = BTreeMap::new();
+        for (name, required) in self.required.iter() {
+            match TestItem::iter().find(|t| t.info().id == *name) {
+                Some(test_item) => {
+                    overrides.insert(test_item, *required);
+                }
+                None => return Err(Error::Config(format!("Unknown test {}", name))),
+            }
+        }
+        Ok(overrides)
+    }
+
+    // The declared profile for this relay, if any
+    pub fn profile_for(&self, relay: &RelayConfig) -> Result<Option<Profile>, Error> {
+        match relay.profile.as_ref().or(self.profile.as_ref()) {
+            Some(name) => Ok(Some(Self::parse_profile(name)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn parse_profile(name: &str) -> Result<Profile, Error> {
+        Profile::from_name(name).ok_or_else(|| Error::Config(format!("Unknown profile {}", name)))
+    }
+}
@@ -1,16 +1,15 @@
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::metrics::Operation;
+use crate::transcript::Direction;
 use base64::Engine;
-use colorful::{Color, Colorful};
 use futures_util::{SinkExt, StreamExt};
 use http::Uri;
 use nostr_types::{ClientMessage, Event, EventKind, Filter, Id, RelayMessage, SubscriptionId, Tag};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tungstenite::Message;
 
-const WAIT_SECONDS: u64 = 3;
-
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum AuthState {
     #[default]
@@ -38,9 +37,21 @@ pub struct FetchResult {
     // It relay closed our subscription, this is the message.
     // If this is None, we timed out
     pub close_msg: Option<String>,
+
+    // The EVENT frames for our subscription exactly as they came over the
+    // wire, in the order received, keyed by the id of the event they carry.
+    pub raw_events: Vec<(Id, String)>,
 }
 
 impl FetchResult {
+    // The raw EVENT frame that carried this event, if any
+    pub fn raw_event(&self, id: Id) -> Option<&str> {
+        self.raw_events
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, raw)| raw.as_str())
+    }
+
     pub fn into_events(self) -> Vec<Event> {
         let mut v: Vec<Event> = self.pre_eose_events;
         if let Some(post) = self.post_eose_events {
@@ -52,11 +63,28 @@ impl FetchResult {
 
 #[derive(Debug)]
 pub struct Connection {
+    // How this connection is labelled in transcripts
+    pub name: String,
+
     pub relay_url: String,
     pub websocket: Ws,
     pub auth_state: AuthState,
     pub dup_auth: bool,
+
+    // Every challenge the relay sent on this connection, in order
+    pub challenges: Vec<String>,
+
+    // Challenges sent on our earlier connections to this relay
+    pub past_challenges: Vec<String>,
+
     pub next_sub_id: AtomicUsize,
+
+    // When the websocket was opened
+    pub connected_at: Instant,
+
+    // Set when the relay closed the websocket or a send failed. The next
+    // send reconnects.
+    pub disconnected: bool,
 }
 
 impl Connection {
@@ -76,27 +104,40 @@ impl Connection {
             .uri(uri)
             .body(())?;
 
+        let connect_timeout = GLOBALS.timeouts.read().connect();
+        let started = Instant::now();
         let (websocket, _response) = tokio::time::timeout(
-            Duration::new(5, 0),
+            connect_timeout,
             tokio_tungstenite::connect_async(request),
         )
         .await??;
+        let connected_at = Instant::now();
+        GLOBALS
+            .metrics
+            .write()
+            .record(Operation::Connect, connected_at - started);
 
         Ok(Connection {
+            name: "main".to_owned(),
             relay_url,
             websocket,
             auth_state: AuthState::NotYetRequested,
             dup_auth: false,
+            challenges: Vec::new(),
+            past_challenges: Vec::new(),
             next_sub_id: AtomicUsize::new(0),
+            connected_at,
+            disconnected: false,
         })
     }
 
     // Dont call if we are still connected
     pub async fn reconnect(&mut self) -> Result<(), Error> {
-        eprintln!("{}", "*** RECONNECTING ***".color(Color::Red));
+        tracing::info!(relay = %self.relay_url, "Reconnecting");
 
         // Wait for a few seconds before reconnecting
-        tokio::time::sleep(Duration::from_secs(WAIT_SECONDS)).await;
+        let delay = GLOBALS.timeouts.read().reconnect();
+        tokio::time::sleep(delay).await;
 
         let (host, uri) = url_to_host_and_uri(&self.relay_url);
         let key: [u8; 16] = rand::random();
@@ -113,41 +154,59 @@ impl Connection {
             .uri(uri)
             .body(())?;
 
+        let connect_timeout = GLOBALS.timeouts.read().connect();
+        let started = Instant::now();
         let (websocket, _response) = tokio::time::timeout(
-            Duration::new(5, 0),
+            connect_timeout,
             tokio_tungstenite::connect_async(request),
         )
         .await??;
+        let connected_at = Instant::now();
+        GLOBALS
+            .metrics
+            .write()
+            .record(Operation::Connect, connected_at - started);
 
         // Sleep a bit for the handshake to finish, else we can end up with
         // "Websocket: WebSocket protocol error: Handshake not finished"
         tokio::time::sleep(Duration::from_millis(250)).await;
 
-        GLOBALS.disconnected.store(false, Ordering::Relaxed);
+        self.disconnected = false;
 
         self.websocket = websocket;
         self.auth_state = AuthState::NotYetRequested;
         self.dup_auth = false;
+        let challenges = std::mem::take(&mut self.challenges);
+        self.past_challenges.extend(challenges);
         self.next_sub_id = AtomicUsize::new(0);
+        self.connected_at = connected_at;
 
         Ok(())
     }
 
     pub async fn disconnect(&mut self) -> Result<(), Error> {
         let msg = Message::Close(None);
-        eprintln!("{} CLOSING", "-->".color(Color::Khaki1));
+        self.record(Direction::Sent, "CLOSE");
         let _ = self.inner_send_message(msg).await;
-        GLOBALS.disconnected.store(true, Ordering::Relaxed);
+        self.disconnected = true;
         Ok(())
     }
 
+    fn record(&self, direction: Direction, text: &str) {
+        tracing::trace!(connection = %self.name, ?direction, "{}", text);
+        GLOBALS
+            .transcript
+            .write()
+            .record(&self.name, direction, text);
+    }
+
     async fn inner_send_message(&mut self, msg: tungstenite::Message) -> Result<(), Error> {
-        if GLOBALS.disconnected.load(Ordering::Relaxed) {
+        if self.disconnected {
             self.reconnect().await?;
         }
 
         if let Err(e) = self.websocket.send(msg).await {
-            GLOBALS.disconnected.store(true, Ordering::Relaxed);
+            self.disconnected = true;
             Err(e)?
         } else {
             Ok(())
@@ -156,17 +215,19 @@ impl Connection {
 
     pub async fn send_message(&mut self, message: ClientMessage) -> Result<(), Error> {
         let wire = serde_json::to_string(&message)?;
+        self.send_raw_message(wire).await
+    }
+
+    // For hand-crafted messages that ClientMessage cannot express. The frame
+    // is recorded before sending, so that a send which fails still shows up.
+    pub async fn send_raw_message(&mut self, wire: String) -> Result<(), Error> {
+        self.record(Direction::Sent, &wire);
         let msg = Message::Text(wire);
-        let m = format!("{} {msg}", "-->".color(Color::Khaki1));
-        self.inner_send_message(msg).await?;
-        eprintln!("{}", m);
-        Ok(())
+        self.inner_send_message(msg).await
     }
 
-    pub async fn wait_for_message(
-        &mut self,
-        timeout: Duration,
-    ) -> Result<Option<RelayMessage>, Error> {
+    // Wait for the next text frame, without parsing it
+    pub async fn wait_for_text(&mut self, timeout: Duration) -> Result<Option<String>, Error> {
         let mut timeout = tokio::time::interval(timeout);
         timeout.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         timeout.tick().await; // use up the first immediate tick.
@@ -180,56 +241,32 @@ impl Connection {
                     let message = match message {
                         Some(m) => m,
                         None => {
-                            GLOBALS.disconnected.store(true, Ordering::Relaxed);
-                            tokio::time::sleep(Duration::from_secs(WAIT_SECONDS)).await;
+                            self.disconnected = true;
+                            let delay = GLOBALS.timeouts.read().reconnect();
+                            tokio::time::sleep(delay).await;
                             return Err(Error::Disconnected);
                         }
                     }?;
 
-                    // Self::display(message.clone())?;
-
-                    // Take action
                     match message {
                         Message::Text(s) => {
-                            eprintln!("{} {s}", "<--".color(Color::MediumPurple2a));
-                            let output: RelayMessage = serde_json::from_str(&s)?;
-
-                            match output {
-                                RelayMessage::Auth(challenge) => {
-                                    match self.auth_state {
-                                        AuthState::NotYetRequested => self.auth_state = AuthState::Challenged(challenge),
-                                        _ => self.dup_auth = true,
-                                    }
-
-                                    // This wasn't the message being waited for, so keep waiting
-                                    continue;
-                                },
-                                RelayMessage::Ok(id, is_ok, ref reason) => {
-                                    GLOBALS.saw_ok_after_event.store(true, Ordering::Relaxed);
-                                    if let AuthState::InProgress(sent_id) = self.auth_state {
-                                        if id == sent_id {
-                                            self.auth_state = if is_ok {
-                                                AuthState::Success
-                                            } else {
-                                                AuthState::Failure(reason.clone())
-                                            };
-
-                                            // This wasn't the message being waited for, so keep waiting
-                                            continue;
-                                        }
-                                    }
-                                },
-                                _ => { }
-                            }
-
-                            return Ok(Some(output));
+                            self.record(Direction::Received, &s);
+                            return Ok(Some(s));
+                        },
+                        Message::Binary(_) => {
+                            tracing::trace!(connection = %self.name, "binary frame");
+                        },
+                        Message::Ping(_) => {
+                            tracing::trace!(connection = %self.name, "ping");
+                        },
+                        Message::Pong(_) => {
+                            tracing::trace!(connection = %self.name, "pong");
                         },
-                        Message::Binary(_) => { },
-                        Message::Ping(_) => { },
-                        Message::Pong(_) => { },
                         Message::Close(_) => {
-                            GLOBALS.disconnected.store(true, Ordering::Relaxed);
-                            tokio::time::sleep(Duration::from_secs(WAIT_SECONDS)).await;
+                            self.record(Direction::Received, "CLOSE");
+                            self.disconnected = true;
+                            let delay = GLOBALS.timeouts.read().reconnect();
+                            tokio::time::sleep(delay).await;
                             return Err(Error::Disconnected);
                         },
                         Message::Frame(_) => unreachable!(),
@@ -239,22 +276,76 @@ impl Connection {
         }
     }
 
+    pub async fn wait_for_message(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<RelayMessage>, Error> {
+        Ok(self
+            .wait_for_message_and_raw(timeout)
+            .await?
+            .map(|(message, _)| message))
+    }
+
+    // Like wait_for_message(), but also returns the text frame the message
+    // was parsed from
+    pub async fn wait_for_message_and_raw(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<(RelayMessage, String)>, Error> {
+        loop {
+            let s = match self.wait_for_text(timeout).await? {
+                Some(s) => s,
+                None => return Ok(None),
+            };
+
+            let output: RelayMessage = serde_json::from_str(&s)?;
+
+            // Take action
+            match output {
+                RelayMessage::Auth(challenge) => {
+                    if self.challenges.is_empty() {
+                        GLOBALS
+                            .metrics
+                            .write()
+                            .record(Operation::FirstAuthChallenge, self.connected_at.elapsed());
+                    }
+                    self.challenges.push(challenge.clone());
+                    match self.auth_state {
+                        AuthState::NotYetRequested => {
+                            self.auth_state = AuthState::Challenged(challenge)
+                        }
+                        _ => self.dup_auth = true,
+                    }
+
+                    // This wasn't the message being waited for, so keep waiting
+                    continue;
+                }
+                RelayMessage::Ok(id, is_ok, ref reason) => {
+                    GLOBALS.saw_ok_after_event.store(true, Ordering::Relaxed);
+                    if let AuthState::InProgress(sent_id) = self.auth_state {
+                        if id == sent_id {
+                            self.auth_state = if is_ok {
+                                AuthState::Success
+                            } else {
+                                AuthState::Failure(reason.clone())
+                            };
+
+                            // This wasn't the message being waited for, so keep waiting
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            return Ok(Some((output, s)));
+        }
+    }
+
     pub async fn authenticate_registered_if_challenged(&mut self) -> Result<(), Error> {
         if let AuthState::Challenged(challenge) = &self.auth_state {
-            let event = Globals::make_event(
-                EventParts::Basic(
-                    EventKind::Auth,
-                    vec![
-                        Tag::new(&["relay", &self.relay_url]),
-                        Tag::new(&["challenge", challenge]),
-                    ],
-                    "".to_string(),
-                ),
-                true,
-            )?;
-            self.auth_state = AuthState::InProgress(event.id);
-            self.send_message(ClientMessage::Auth(Box::new(event)))
-                .await?;
+            let challenge = challenge.clone();
+            self.send_auth(&challenge, true).await?;
             let _ = self.wait_for_message(Duration::from_secs(1)).await?; // to await response
         }
         Ok(())
@@ -262,25 +353,80 @@ impl Connection {
 
     pub async fn authenticate_stranger_if_challenged(&mut self) -> Result<(), Error> {
         if let AuthState::Challenged(challenge) = &self.auth_state {
-            let event = Globals::make_event(
-                EventParts::Basic(
-                    EventKind::Auth,
-                    vec![
-                        Tag::new(&["relay", &self.relay_url]),
-                        Tag::new(&["challenge", challenge]),
-                    ],
-                    "".to_string(),
-                ),
-                false,
-            )?;
-            self.auth_state = AuthState::InProgress(event.id);
-            self.send_message(ClientMessage::Auth(Box::new(event)))
-                .await?;
+            let challenge = challenge.clone();
+            self.send_auth(&challenge, false).await?;
             let _ = self.wait_for_message(Duration::from_secs(1)).await?; // to await response
         }
         Ok(())
     }
 
+    // Authenticate now, as the registered user or the stranger, rather than
+    // waiting for a stage to do it. If the relay has not challenged us yet we
+    // wait up to the timeout for a challenge. Returns the resulting state,
+    // which is still NotYetRequested if no challenge came.
+    pub async fn authenticate(
+        &mut self,
+        registered: bool,
+        timeout: Duration,
+    ) -> Result<AuthState, Error> {
+        if self.auth_state == AuthState::NotYetRequested {
+            let _ = self.wait_for_message(timeout).await?;
+        }
+
+        let challenge = match &self.auth_state {
+            AuthState::Challenged(challenge) => challenge.clone(),
+            state => return Ok(state.clone()),
+        };
+
+        let event = Self::make_auth_event(&self.relay_url, &challenge, registered)?;
+        self.authenticate_with(event, timeout).await
+    }
+
+    // Send this AUTH event, whatever it contains, and wait for the relay's
+    // verdict on it. Returns InProgress if the relay never answered.
+    pub async fn authenticate_with(
+        &mut self,
+        event: Event,
+        timeout: Duration,
+    ) -> Result<AuthState, Error> {
+        self.auth_state = AuthState::InProgress(event.id);
+        self.send_message(ClientMessage::Auth(Box::new(event)))
+            .await?;
+        while let AuthState::InProgress(_) = self.auth_state {
+            if self.wait_for_message(timeout).await?.is_none() {
+                break;
+            }
+        }
+
+        Ok(self.auth_state.clone())
+    }
+
+    async fn send_auth(&mut self, challenge: &str, registered: bool) -> Result<(), Error> {
+        let event = Self::make_auth_event(&self.relay_url, challenge, registered)?;
+        self.auth_state = AuthState::InProgress(event.id);
+        self.send_message(ClientMessage::Auth(Box::new(event)))
+            .await
+    }
+
+    // A correct NIP-42 AUTH event for this relay and challenge
+    pub fn make_auth_event(
+        relay_url: &str,
+        challenge: &str,
+        registered: bool,
+    ) -> Result<Event, Error> {
+        Globals::make_event(
+            EventParts::Basic(
+                EventKind::Auth,
+                vec![
+                    Tag::new(&["relay", relay_url]),
+                    Tag::new(&["challenge", challenge]),
+                ],
+                "".to_string(),
+            ),
+            registered,
+        )
+    }
+
     pub async fn fetch_events(
         &mut self,
         filters: Vec<Filter>,
@@ -303,17 +449,46 @@ impl Connection {
         timeout: Duration,
         close: bool,
     ) -> Result<FetchResult, Error> {
-        let sub_id_usize = self.next_sub_id.fetch_add(1, Ordering::Relaxed);
-        let sub_id = SubscriptionId(format!("sub{}", sub_id_usize));
+        let sub_id = self.next_subscription_id();
         let client_message = ClientMessage::Req(sub_id.clone(), filters);
+        let sent = Instant::now();
         self.send_message(client_message).await?;
+        self.collect_fetch_result(sub_id, sent, timeout, close).await
+    }
 
+    // Like fetch_events(), but each filter is given as hand-written JSON so
+    // that filters which nostr_types::Filter cannot express can be sent.
+    pub async fn fetch_events_raw(
+        &mut self,
+        filters: &[&str],
+        timeout: Duration,
+    ) -> Result<FetchResult, Error> {
+        let sub_id = self.next_subscription_id();
+        let wire = format!("[\"REQ\",\"{}\",{}]", sub_id.0, filters.join(","));
+        let sent = Instant::now();
+        self.send_raw_message(wire).await?;
+        self.collect_fetch_result(sub_id, sent, timeout, true).await
+    }
+
+    pub fn next_subscription_id(&self) -> SubscriptionId {
+        let sub_id_usize = self.next_sub_id.fetch_add(1, Ordering::Relaxed);
+        SubscriptionId(format!("sub{}", sub_id_usize))
+    }
+
+    async fn collect_fetch_result(
+        &mut self,
+        sub_id: SubscriptionId,
+        sent: Instant,
+        timeout: Duration,
+        close: bool,
+    ) -> Result<FetchResult, Error> {
         let mut pre_eose_events: Vec<Event> = Vec::new();
         let mut post_eose_events: Vec<Event> = Vec::new();
+        let mut raw_events: Vec<(Id, String)> = Vec::new();
         let mut eose_happened: bool = false;
 
         loop {
-            let opt_message = self.wait_for_message(timeout).await?;
+            let opt_message = self.wait_for_message_and_raw(timeout).await?;
             if opt_message.is_none() {
                 // Close the subscription
                 if close {
@@ -326,6 +501,7 @@ impl Connection {
                         pre_eose_events,
                         post_eose_events: Some(post_eose_events),
                         close_msg: None,
+                        raw_events,
                     });
                 } else {
                     return Ok(FetchResult {
@@ -333,12 +509,15 @@ impl Connection {
                         pre_eose_events,
                         post_eose_events: None,
                         close_msg: None,
+                        raw_events,
                     });
                 }
             }
-            match opt_message.unwrap() {
+            let (message, raw) = opt_message.unwrap();
+            match message {
                 RelayMessage::Event(sub, box_event) => {
                     if sub == sub_id {
+                        raw_events.push((box_event.id, raw));
                         if eose_happened {
                             post_eose_events.push((*box_event).clone());
                         } else {
@@ -354,6 +533,7 @@ impl Connection {
                                 pre_eose_events,
                                 post_eose_events: Some(post_eose_events),
                                 close_msg: Some(msg),
+                                raw_events,
                             });
                         } else {
                             return Ok(FetchResult {
@@ -361,12 +541,19 @@ impl Connection {
                                 pre_eose_events,
                                 post_eose_events: None,
                                 close_msg: Some(msg),
+                                raw_events,
                             });
                         }
                     }
                 }
                 RelayMessage::Eose(sub) => {
                     if sub == sub_id {
+                        if !eose_happened {
+                            GLOBALS
+                                .metrics
+                                .write()
+                                .record(Operation::TimeToEose, sent.elapsed());
+                        }
                         eose_happened = true;
                     }
                 }
@@ -408,20 +595,9 @@ impl Connection {
     ) -> Result<(bool, String), Error> {
         let event_id = event.id;
         let message = ClientMessage::Event(Box::new(event));
+        let sent = Instant::now();
         self.send_message(message).await?;
-        loop {
-            match self.wait_for_message(timeout).await? {
-                None => return Err(Error::TimedOut),
-                Some(RelayMessage::Ok(id, ok, msg)) => {
-                    if id != event_id {
-                        continue;
-                    }
-                    GLOBALS.saw_ok_after_event.store(true, Ordering::Relaxed);
-                    return Ok((ok, msg));
-                }
-                Some(_) => continue,
-            }
-        }
+        self.wait_for_ok(event_id, sent, timeout).await
     }
 
     pub async fn post_raw_event(
@@ -431,9 +607,38 @@ impl Connection {
         timeout: Duration,
     ) -> Result<(bool, String), Error> {
         let wire = format!("[\"EVENT\",{}]", json);
-        let msg = Message::Text(wire);
-        eprintln!("{} {msg}", "-->".color(Color::Khaki1));
-        self.inner_send_message(msg).await?;
+        let sent = Instant::now();
+        self.send_raw_message(wire).await?;
+        self.wait_for_ok(event_id, sent, timeout).await
+    }
+
+    // Like post_raw_event(), but the event need not be valid UTF-8. It is
+    // still sent in a text frame.
+    pub async fn post_raw_event_bytes(
+        &mut self,
+        event_id: Id,
+        json: &[u8],
+        timeout: Duration,
+    ) -> Result<(bool, String), Error> {
+        use tungstenite::protocol::frame::coding::{Data, OpCode};
+        use tungstenite::protocol::frame::Frame;
+
+        let mut wire: Vec<u8> = b"[\"EVENT\",".to_vec();
+        wire.extend_from_slice(json);
+        wire.push(b']');
+        self.record(Direction::Sent, &String::from_utf8_lossy(&wire));
+        let frame = Frame::message(wire, OpCode::Data(Data::Text), true);
+        let sent = Instant::now();
+        self.inner_send_message(Message::Frame(frame)).await?;
+        self.wait_for_ok(event_id, sent, timeout).await
+    }
+
+    async fn wait_for_ok(
+        &mut self,
+        event_id: Id,
+        sent: Instant,
+        timeout: Duration,
+    ) -> Result<(bool, String), Error> {
         loop {
             match self.wait_for_message(timeout).await? {
                 None => return Err(Error::TimedOut),
@@ -442,6 +647,10 @@ impl Connection {
                         continue;
                     }
                     GLOBALS.saw_ok_after_event.store(true, Ordering::Relaxed);
+                    GLOBALS
+                        .metrics
+                        .write()
+                        .record(Operation::OkRoundTrip, sent.elapsed());
                     return Ok((ok, msg));
                 }
                 Some(_) => continue,
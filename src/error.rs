@@ -3,8 +3,10 @@ use std::fmt;
 
 #[derive(Debug)]
 pub enum Error {
+    Config(String),
     Disconnected,
     Http(http::Error),
+    Io(std::io::Error),
     Join(tokio::task::JoinError),
     Json(serde_json::Error),
     NostrTypes(nostr_types::Error),
@@ -18,8 +20,10 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
+            Error::Config(s) => write!(f, "Config: {s}"),
             Error::Disconnected => write!(f, "Disconnected"),
             Error::Http(e) => write!(f, "Http: {e}"),
+            Error::Io(e) => write!(f, "I/O: {e}"),
             Error::Join(e) => write!(f, "Tokio join: {e}"),
             Error::Json(e) => write!(f, "JSON: {e}"),
             Error::NostrTypes(e) => write!(f, "nostr-types: {e}"),
@@ -38,6 +42,7 @@ impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
             Error::Http(inner) => Some(inner),
+            Error::Io(inner) => Some(inner),
             Error::Join(inner) => Some(inner),
             Error::Json(inner) => Some(inner),
             Error::NostrTypes(inner) => Some(inner),
@@ -55,6 +60,12 @@ impl From<http::Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
 impl From<tokio::task::JoinError> for Error {
     fn from(e: tokio::task::JoinError) -> Error {
         Error::Join(e)
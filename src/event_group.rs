@@ -1,10 +1,9 @@
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
-use crate::WAIT;
+use crate::wait;
 use nostr_types::Event;
 use std::collections::HashMap;
 use std::slice::Iter;
-use std::time::Duration;
 
 pub struct EventGroup {
     // Events, and whether they can_read_back
@@ -37,7 +36,7 @@ impl EventGroup {
             .write()
             .as_mut()
             .unwrap()
-            .post_event(event.clone(), Duration::from_secs(WAIT))
+            .post_event(event.clone(), wait())
             .await?;
 
         // Insert into the event group
@@ -0,0 +1,330 @@
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::oracle::{Oracle, OracleFilter};
+use crate::wait;
+use colorful::{Color, Colorful};
+use nostr_types::{Event, EventKind, PrivateKey, PublicKey, Signer, Tag, Unixtime};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+pub const DEFAULT_NUM_EVENTS: usize = 100;
+pub const DEFAULT_NUM_FILTERS: usize = 50;
+
+// Kinds the fuzzer draws from. These are chosen to be unlikely to have
+// kind-specific validation in relays.
+const REGULAR_KINDS: [u32; 3] = [1, 7, 7777];
+const REPLACEABLE_KINDS: [u32; 2] = [10101, 10102];
+const ADDRESSABLE_KINDS: [u32; 2] = [30101, 30102];
+const EPHEMERAL_KINDS: [u32; 1] = [21101];
+
+const TAG_LETTERS: [char; 2] = ['t', 'x'];
+const TAG_VALUES: [&str; 4] = ["a", "b", "c", "d"];
+const D_VALUES: [&str; 3] = ["x", "y", "z"];
+
+// Seconds between the (shuffled) created_at values of generated events.
+// Every event gets a distinct timestamp so result ordering is unambiguous.
+const SPACING: i64 = 7;
+
+#[derive(Debug)]
+enum Discrepancy {
+    // The oracle expected this event but the relay did not return it
+    Missing(Event),
+
+    // The relay returned this event but the oracle did not expect it
+    Unexpected(Event),
+}
+
+impl Discrepancy {
+    fn event(&self) -> &Event {
+        match self {
+            Discrepancy::Missing(e) => e,
+            Discrepancy::Unexpected(e) => e,
+        }
+    }
+
+    fn still_present(&self, relay: &[Event], expected: &[Event]) -> bool {
+        let id = self.event().id;
+        let in_relay = relay.iter().any(|e| e.id == id);
+        let in_expected = expected.iter().any(|e| e.id == id);
+        match self {
+            Discrepancy::Missing(_) => in_expected && !in_relay,
+            Discrepancy::Unexpected(_) => in_relay && !in_expected,
+        }
+    }
+}
+
+/// Publish random-but-valid events as the stranger, then issue random filters
+/// and compare the relay's answers against the oracle. The seed reproduces the
+/// sequence of event and filter shapes (keys are fresh on every run).
+pub async fn run(seed: u64, num_events: usize, num_filters: usize) -> Result<(), Error> {
+    tracing::info!("Fuzzing with seed {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // Events are published by the stranger, whose key is fresh on every run,
+    // so nothing else on the relay can match our filters.
+    {
+        let mut con = GLOBALS.connection.write();
+        let _ = con
+            .as_mut()
+            .unwrap()
+            .wait_for_message(Duration::from_secs(1))
+            .await?;
+        con.as_mut()
+            .unwrap()
+            .authenticate_stranger_if_challenged()
+            .await?;
+    }
+    let author = GLOBALS.stranger.read().public_key();
+
+    let mut oracle = Oracle::new();
+    let mut published: Vec<Event> = Vec::new();
+    let mut rejected: usize = 0;
+
+    let now = Unixtime::now().0;
+    let mut offsets: Vec<i64> = (1..=num_events as i64).map(|i| i * SPACING).collect();
+    offsets.shuffle(&mut rng);
+
+    for offset in offsets {
+        // Occasionally resubmit something we already published
+        let event = if !published.is_empty() && rng.gen_bool(0.05) {
+            published.choose(&mut rng).unwrap().clone()
+        } else {
+            let parts = random_event_parts(&mut rng, &published, Unixtime(now - offset));
+            Globals::make_event(parts, false)?
+        };
+
+        let (ok, reason) = GLOBALS
+            .connection
+            .write()
+            .as_mut()
+            .unwrap()
+            .post_event(event.clone(), wait())
+            .await?;
+
+        if ok {
+            oracle.insert(&event);
+        } else {
+            rejected += 1;
+            tracing::info!(
+                "Kind {} rejected: {}",
+                Into::<u32>::into(event.kind),
+                reason
+            );
+        }
+        published.push(event);
+    }
+
+    let span = num_events as i64 * SPACING;
+    let mut failures: usize = 0;
+    for _ in 0..num_filters {
+        let filter = random_filter(&mut rng, &published, author, now, span);
+        let relay = query_relay(&filter).await?;
+        let expected = oracle.query(&filter);
+
+        let discrepancy = match find_discrepancy(&relay, &expected) {
+            Some(d) => d,
+            None => continue,
+        };
+
+        failures += 1;
+        let minimal = minimize(filter, &discrepancy, &oracle).await?;
+        report(failures, &minimal, &discrepancy)?;
+    }
+
+    println!("FUZZ RESULTS (seed {})\n", seed);
+    println!(
+        "Published {} events ({} rejected)",
+        published.len(),
+        rejected
+    );
+    println!("Checked {} filters", num_filters);
+    if failures == 0 {
+        println!("{}", "No discrepancies found".color(Color::Green));
+    } else {
+        println!(
+            "{}",
+            format!("{} filters disagreed with the oracle", failures).color(Color::Red3a)
+        );
+    }
+
+    Ok(())
+}
+
+fn random_event_parts(rng: &mut StdRng, published: &[Event], created_at: Unixtime) -> EventParts {
+    let roll: u32 = rng.gen_range(0..100);
+
+    // Deletions of something we published earlier
+    if roll < 10 && !published.is_empty() {
+        let target = published.choose(rng).unwrap();
+        let target_kind: u32 = target.kind.into();
+        let tag = if crate::oracle::is_addressable(target_kind) && rng.gen_bool(0.5) {
+            Tag::new(&["a", &crate::oracle::address_of(target)])
+        } else {
+            Tag::new(&["e", &target.id.as_hex_string()])
+        };
+        return EventParts::Dated(
+            EventKind::EventDeletion,
+            vec![tag],
+            "".to_owned(),
+            created_at,
+        );
+    }
+
+    let kind: u32 = if roll < 60 {
+        *REGULAR_KINDS.choose(rng).unwrap()
+    } else if roll < 75 {
+        *REPLACEABLE_KINDS.choose(rng).unwrap()
+    } else if roll < 95 {
+        *ADDRESSABLE_KINDS.choose(rng).unwrap()
+    } else {
+        *EPHEMERAL_KINDS.choose(rng).unwrap()
+    };
+
+    let mut tags: Vec<Tag> = Vec::new();
+    if crate::oracle::is_addressable(kind) {
+        tags.push(Tag::new(&["d", *D_VALUES.choose(rng).unwrap()]));
+    }
+    for _ in 0..rng.gen_range(0..4) {
+        let letter = TAG_LETTERS.choose(rng).unwrap().to_string();
+        tags.push(Tag::new(&[
+            letter.as_str(),
+            *TAG_VALUES.choose(rng).unwrap(),
+        ]));
+    }
+
+    let content: String = (0..rng.gen_range(0..40))
+        .map(|_| rng.gen_range('a'..='z'))
+        .collect();
+
+    EventParts::Dated(EventKind::from(kind), tags, content, created_at)
+}
+
+fn random_filter(
+    rng: &mut StdRng,
+    published: &[Event],
+    author: PublicKey,
+    now: i64,
+    span: i64,
+) -> OracleFilter {
+    let mut filter = OracleFilter::default();
+
+    // Always constrain by ids or authors so only our own events can match
+    if !published.is_empty() && rng.gen_bool(0.2) {
+        for _ in 0..rng.gen_range(1..=3) {
+            filter.ids.push(published.choose(rng).unwrap().id);
+        }
+    } else {
+        filter.authors.push(author);
+        if rng.gen_bool(0.2) {
+            filter.authors.push(PrivateKey::generate().public_key());
+        }
+    }
+
+    if rng.gen_bool(0.5) {
+        let all_kinds: Vec<u32> = REGULAR_KINDS
+            .iter()
+            .chain(REPLACEABLE_KINDS.iter())
+            .chain(ADDRESSABLE_KINDS.iter())
+            .chain(EPHEMERAL_KINDS.iter())
+            .chain([5].iter())
+            .copied()
+            .collect();
+        for _ in 0..rng.gen_range(1..=2) {
+            filter
+                .kinds
+                .push(EventKind::from(*all_kinds.choose(rng).unwrap()));
+        }
+    }
+
+    if rng.gen_bool(0.4) {
+        let letter = if rng.gen_bool(0.2) {
+            'd'
+        } else {
+            *TAG_LETTERS.choose(rng).unwrap()
+        };
+        let pool: &[&str] = if letter == 'd' {
+            &D_VALUES
+        } else {
+            &TAG_VALUES
+        };
+        let values: Vec<String> = (0..rng.gen_range(1..=2))
+            .map(|_| pool.choose(rng).unwrap().to_string())
+            .collect();
+        filter.tags.push((letter, values));
+    }
+
+    if rng.gen_bool(0.3) {
+        filter.since = Some(Unixtime(now - rng.gen_range(0..=span)));
+    }
+    if rng.gen_bool(0.3) {
+        filter.until = Some(Unixtime(now - rng.gen_range(0..=span)));
+    }
+    if rng.gen_bool(0.3) {
+        filter.limit = Some(rng.gen_range(0..=10));
+    }
+
+    filter
+}
+
+async fn query_relay(filter: &OracleFilter) -> Result<Vec<Event>, Error> {
+    Ok(GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter.to_filter()], wait())
+        .await?
+        .into_events())
+}
+
+fn find_discrepancy(relay: &[Event], expected: &[Event]) -> Option<Discrepancy> {
+    for event in expected.iter() {
+        if !relay.iter().any(|e| e.id == event.id) {
+            return Some(Discrepancy::Missing(event.clone()));
+        }
+    }
+    for event in relay.iter() {
+        if !expected.iter().any(|e| e.id == event.id) {
+            return Some(Discrepancy::Unexpected(event.clone()));
+        }
+    }
+    None
+}
+
+// Greedily drop filter constraints while the same discrepancy persists.
+async fn minimize(
+    filter: OracleFilter,
+    discrepancy: &Discrepancy,
+    oracle: &Oracle,
+) -> Result<OracleFilter, Error> {
+    let mut current = filter;
+    'outer: loop {
+        for candidate in current.reductions() {
+            let relay = query_relay(&candidate).await?;
+            let expected = oracle.query(&candidate);
+            if discrepancy.still_present(&relay, &expected) {
+                current = candidate;
+                continue 'outer;
+            }
+        }
+        return Ok(current);
+    }
+}
+
+fn report(n: usize, filter: &OracleFilter, discrepancy: &Discrepancy) -> Result<(), Error> {
+    let what = match discrepancy {
+        Discrepancy::Missing(_) => "missing from relay results",
+        Discrepancy::Unexpected(_) => "returned by relay but not expected",
+    };
+    println!(
+        "{} #{}: event {}",
+        "DISCREPANCY".color(Color::Red3a),
+        n,
+        what
+    );
+    println!("  filter: {}", serde_json::to_string(&filter.to_filter())?);
+    println!("  event:  {}", serde_json::to_string(discrepancy.event())?);
+    Ok(())
+}
@@ -1,14 +1,17 @@
+use crate::config::Timeouts;
 use crate::connection::Connection;
 use crate::error::Error;
 use crate::event_group::EventGroup;
+use crate::metrics::Metrics;
 use crate::outcome::Outcome;
+use crate::profile::Profile;
 use crate::test_item::TestItem;
-use colorful::{Color, Colorful};
+use crate::transcript::Transcript;
 use lazy_static::lazy_static;
 use nostr_types::{Event, EventKind, Id, KeySigner, PreEvent, PrivateKey, Signer, Tag, Unixtime};
 use parking_lot::RwLock;
 use std::collections::BTreeMap;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use strum::IntoEnumIterator;
 
@@ -19,7 +22,6 @@ lazy_static! {
 pub struct Globals {
     pub relay_url: Arc<RwLock<String>>,
     pub connection: Arc<RwLock<Option<Connection>>>,
-    pub disconnected: AtomicBool,
     pub stranger: Arc<RwLock<KeySigner>>,
     pub registered_user: Arc<RwLock<KeySigner>>,
     pub test_results: Arc<RwLock<BTreeMap<TestItem, Outcome>>>,
@@ -28,6 +30,11 @@ pub struct Globals {
     pub event_group_a: Arc<RwLock<EventGroup>>,
     pub event_group_a_submitted: AtomicBool,
     pub event_group_a_failed: AtomicBool,
+    pub profile: Arc<RwLock<Option<Profile>>>,
+    pub timeouts: Arc<RwLock<Timeouts>>,
+    pub required_overrides: Arc<RwLock<BTreeMap<TestItem, bool>>>,
+    pub metrics: Arc<RwLock<Metrics>>,
+    pub transcript: Arc<RwLock<Transcript>>,
 }
 
 impl Globals {
@@ -40,7 +47,6 @@ impl Globals {
         Globals {
             relay_url: Arc::new(RwLock::new("".to_owned())),
             connection: Arc::new(RwLock::new(None)),
-            disconnected: AtomicBool::new(false),
             stranger: Arc::new(RwLock::new(KeySigner::generate("stranger", 2).unwrap())),
             registered_user: Arc::new(RwLock::new(KeySigner::generate("fixme", 2).unwrap())),
             test_results: Arc::new(RwLock::new(test_results)),
@@ -49,19 +55,40 @@ impl Globals {
             event_group_a: Arc::new(RwLock::new(EventGroup::new())),
             event_group_a_submitted: AtomicBool::new(false),
             event_group_a_failed: AtomicBool::new(false),
+            profile: Arc::new(RwLock::new(None)),
+            timeouts: Arc::new(RwLock::new(Timeouts::default())),
+            required_overrides: Arc::new(RwLock::new(BTreeMap::new())),
+            metrics: Arc::new(RwLock::new(Metrics::default())),
+            transcript: Arc::new(RwLock::new(Transcript::default())),
         }
     }
 
     pub async fn init(relay_url: String, private_key: PrivateKey) -> Result<(), Error> {
+        Self::reset();
         *GLOBALS.relay_url.write() = relay_url;
         *GLOBALS.registered_user.write() = KeySigner::from_private_key(private_key, "", 8).unwrap();
-        eprintln!("{}", "*** CONNECTING ***".color(Color::Red));
         let relay_url = GLOBALS.relay_url.read().clone();
+        tracing::info!(relay = %relay_url, "Connecting");
         let connection = Connection::new(relay_url).await?;
         *GLOBALS.connection.write() = Some(connection);
         Ok(())
     }
 
+    // Forget everything from testing a previous relay
+    fn reset() {
+        let mut test_results = GLOBALS.test_results.write();
+        for test_item in TestItem::iter() {
+            test_results.insert(test_item, Default::default());
+        }
+        *GLOBALS.stranger.write() = KeySigner::generate("stranger", 2).unwrap();
+        *GLOBALS.nip11.write() = None;
+        GLOBALS.saw_ok_after_event.store(false, Ordering::Relaxed);
+        *GLOBALS.event_group_a.write() = EventGroup::new();
+        GLOBALS.event_group_a_submitted.store(false, Ordering::Relaxed);
+        GLOBALS.event_group_a_failed.store(false, Ordering::Relaxed);
+        *GLOBALS.metrics.write() = Metrics::default();
+    }
+
     pub fn make_event(parts: EventParts, registered: bool) -> Result<Event, Error> {
         let (kind, tags, content, created_at) = match parts {
             EventParts::Basic(k, t, c) => (k, t, c, Unixtime::now()),
@@ -91,6 +118,29 @@ impl Globals {
         tags: &str,
         content: &str,
         registered: bool,
+    ) -> (Id, String) {
+        Self::make_raw_event_custom(
+            created_at,
+            kind,
+            tags,
+            content,
+            registered,
+            &NIP01_FIELD_ORDER,
+            &[],
+        )
+    }
+
+    // Like make_raw_event(), but fields are written in the given order and the
+    // extra (already JSON encoded) fields are included. Fields not named in the
+    // order come afterwards.
+    pub fn make_raw_event_custom(
+        created_at: &str,
+        kind: &str,
+        tags: &str,
+        content: &str,
+        registered: bool,
+        order: &[&str],
+        extra: &[(&str, &str)],
     ) -> (Id, String) {
         let public_key_hex = if registered {
             GLOBALS.registered_user.read().public_key().as_hex_string()
@@ -98,31 +148,71 @@ impl Globals {
             GLOBALS.stranger.read().public_key().as_hex_string()
         };
 
+        let id = Self::raw_event_id(&public_key_hex, created_at, kind, tags, content);
+        let signature = GLOBALS.registered_user.read().sign_id(id).unwrap();
+
+        let id_json = format!("\"{}\"", id.as_hex_string());
+        let pubkey_json = format!("\"{}\"", &public_key_hex);
+        let content_json = format!("\"{}\"", content);
+        let sig_json = format!("\"{}\"", signature.as_hex_string());
+
+        let mut fields: Vec<(&str, &str)> = vec![
+            ("id", id_json.as_str()),
+            ("pubkey", pubkey_json.as_str()),
+            ("created_at", created_at),
+            ("kind", kind),
+            ("tags", tags),
+            ("content", content_json.as_str()),
+            ("sig", sig_json.as_str()),
+        ];
+        fields.extend_from_slice(extra);
+
+        let mut members: Vec<String> = Vec::new();
+        for name in order.iter() {
+            if let Some((k, v)) = fields.iter().find(|(k, _)| k == name) {
+                members.push(format!("\"{}\":{}", k, v));
+            }
+        }
+        for (k, v) in fields.iter() {
+            if !order.contains(k) {
+                members.push(format!("\"{}\":{}", k, v));
+            }
+        }
+
+        let raw_event = format!("{{{}}}", members.join(","));
+
+        (id, raw_event)
+    }
+
+    // The id of an event with these fields, each given exactly as it is
+    // written in the JSON (content without its quotes)
+    pub fn raw_event_id(
+        public_key_hex: &str,
+        created_at: &str,
+        kind: &str,
+        tags: &str,
+        content: &str,
+    ) -> Id {
         let serial_for_sig = format!(
             "[0,\"{}\",{},{},{},\"{}\"]",
-            &public_key_hex, created_at, kind, tags, content
+            public_key_hex, created_at, kind, tags, content
         );
         use secp256k1::hashes::Hash;
         let hash = secp256k1::hashes::sha256::Hash::hash(serial_for_sig.as_bytes());
-        let id: [u8; 32] = hash.to_byte_array();
-        let id = Id(id);
-        let signature = GLOBALS.registered_user.read().sign_id(id).unwrap();
-
-        let raw_event = format!(
-            r##"{{"id":"{}","pubkey":"{}","created_at":{},"kind":{},"tags":{},"content":"{}","sig":"{}"}}"##,
-            id.as_hex_string(),
-            &public_key_hex,
-            created_at,
-            kind,
-            tags,
-            content,
-            signature.as_hex_string()
-        );
-
-        (id, raw_event)
+        Id(hash.to_byte_array())
     }
 }
 
+pub const NIP01_FIELD_ORDER: [&str; 7] = [
+    "id",
+    "pubkey",
+    "created_at",
+    "kind",
+    "tags",
+    "content",
+    "sig",
+];
+
 #[derive(Debug, Clone)]
 pub enum EventParts {
     Basic(EventKind, Vec<Tag>, String),
@@ -0,0 +1,67 @@
+use crate::test_item::TestItem;
+use colorful::{Color, Colorful};
+use serde_json::json;
+use strum::IntoEnumIterator;
+
+/// Print the test catalogue without connecting to any relay. If any NIPs
+/// are given, only the tests that reference one of them are listed.
+pub fn print(as_json: bool, nips: &[u32]) {
+    let test_items: Vec<TestItem> = TestItem::iter()
+        .filter(|t| nips.is_empty() || t.nips().iter().any(|n| nips.contains(n)))
+        .collect();
+
+    if as_json {
+        let entries: Vec<serde_json::Value> = test_items
+            .iter()
+            .map(|t| {
+                let info = t.info();
+                let prerequisites: Vec<&str> =
+                    info.prerequisites.iter().map(|p| p.info().id).collect();
+                json!({
+                    "id": info.id,
+                    "name": info.name,
+                    "stage": format!("{:?}", info.stage),
+                    "required": info.required,
+                    "nips": info.nips,
+                    "tags": info.tags,
+                    "prerequisites": prerequisites,
+                    "implemented": info.implemented,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+        return;
+    }
+
+    for test_item in test_items.iter() {
+        let info = test_item.info();
+        let nips: Vec<String> = info.nips.iter().map(|n| format!("NIP-{:02}", n)).collect();
+        let status = if info.implemented {
+            "implemented".color(Color::Green)
+        } else {
+            "tbd".color(Color::Grey50)
+        };
+        let required = if info.required {
+            "required"
+        } else {
+            "optional"
+        };
+        println!(
+            "{} [{:?}, {}] {} ({}) {}",
+            info.id.color(Color::Cyan),
+            info.stage,
+            required,
+            info.name,
+            nips.join(", "),
+            status
+        );
+    }
+
+    let implemented = test_items.iter().filter(|t| t.info().implemented).count();
+    println!(
+        "\n{} tests, {} implemented, {} still to do",
+        test_items.len(),
+        implemented,
+        test_items.len() - implemented
+    );
+}
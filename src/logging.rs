@@ -0,0 +1,41 @@
+use tracing::level_filters::LevelFilter;
+
+/// How much goes to stderr. The results always go to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    // Nothing but the results, for CI
+    Quiet,
+
+    // Progress, and the wire transcripts of tests that do not pass
+    Normal,
+
+    // The wire transcripts of every test
+    Verbose,
+
+    // Every frame as it happens, including pings
+    Trace,
+}
+
+impl Verbosity {
+    fn level(&self) -> LevelFilter {
+        match *self {
+            Verbosity::Quiet => LevelFilter::ERROR,
+            Verbosity::Normal => LevelFilter::INFO,
+            Verbosity::Verbose => LevelFilter::DEBUG,
+            Verbosity::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+pub fn init(verbosity: Verbosity, json: bool) {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(verbosity.level())
+        .with_writer(std::io::stderr)
+        .with_target(false);
+
+    if json {
+        builder.json().init();
+    } else {
+        builder.without_time().init();
+    }
+}
@@ -1,17 +1,32 @@
 #![allow(clippy::await_holding_lock)] // we aren't really parallel, doesn't matter.
 
+mod bench;
+mod config;
 mod connection;
 mod error;
 mod event_group;
+mod fuzz;
 mod globals;
+mod list;
+mod logging;
+mod metrics;
+mod oracle;
 mod outcome;
+mod profile;
+mod report;
+mod scenario;
 mod stage;
 mod test_item;
 mod tests;
+mod transcript;
 
+use crate::config::Config;
 use crate::error::Error;
 use crate::globals::{Globals, GLOBALS};
+use crate::logging::Verbosity;
+use crate::metrics::Operation;
 use crate::outcome::Outcome;
+use crate::profile::Profile;
 use crate::stage::Stage;
 use crate::test_item::TestItem;
 use colorful::{Color, Colorful};
@@ -19,18 +34,105 @@ use nostr_types::PrivateKey;
 use std::env;
 use strum::IntoEnumIterator;
 
-const WAIT: u64 = 2;
+// How long to wait for a relay to reply before deciding it will not
+fn wait() -> std::time::Duration {
+    GLOBALS.timeouts.read().wait()
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let mut args = env::args();
-    let _ = args.next(); // program name
+    let mut args: Vec<String> = env::args().skip(1).collect(); // skip program name
+
+    // --profile <name> may come anywhere
+    if let Some(i) = args.iter().position(|a| a == "--profile") {
+        if i + 1 >= args.len() {
+            return usage();
+        }
+        let name = args.remove(i + 1);
+        let _ = args.remove(i);
+        match Profile::from_name(&name) {
+            Some(profile) => *GLOBALS.profile.write() = Some(profile),
+            None => return usage(),
+        }
+    }
+
+    // --html <file> may come anywhere
+    let mut html: Option<String> = None;
+    if let Some(i) = args.iter().position(|a| a == "--html") {
+        if i + 1 >= args.len() {
+            return usage();
+        }
+        html = Some(args.remove(i + 1));
+        let _ = args.remove(i);
+    }
+
+    // Logging flags may come anywhere
+    let verbosity = if take_flag(&mut args, "--trace") {
+        Verbosity::Trace
+    } else if take_flag(&mut args, "--verbose") {
+        Verbosity::Verbose
+    } else if take_flag(&mut args, "--quiet") {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    };
+    let log_json = take_flag(&mut args, "--log-json");
+    logging::init(verbosity, log_json);
+
+    let flood = take_flag(&mut args, "--flood");
+    let vanish = take_flag(&mut args, "--vanish");
 
-    let relay_url = match args.next() {
+    // --nip <number> may come anywhere, and more than once
+    let mut nips: Vec<u32> = Vec::new();
+    while let Some(i) = args.iter().position(|a| a == "--nip") {
+        if i + 1 >= args.len() {
+            return usage();
+        }
+        let nip = args.remove(i + 1);
+        let _ = args.remove(i);
+        match nip.parse() {
+            Ok(nip) => nips.push(nip),
+            Err(_) => return usage(),
+        }
+    }
+    let mut args = args.into_iter();
+
+    let mut relay_url = match args.next() {
         Some(u) => u,
         None => return usage(),
     };
 
+    if relay_url == "--list" {
+        return match args.next().as_deref() {
+            None => {
+                list::print(false, &nips);
+                Ok(())
+            }
+            Some("--json") => {
+                list::print(true, &nips);
+                Ok(())
+            }
+            Some(_) => usage(),
+        };
+    }
+
+    if relay_url == "--config" {
+        return match args.next() {
+            Some(path) => run_config(&path, &nips, html.as_deref(), flood, vanish).await,
+            None => usage(),
+        };
+    }
+
+    let fuzz_mode = relay_url == "--fuzz";
+    let scenario_mode = relay_url == "--scenario";
+    let bench_mode = relay_url == "--bench";
+    if fuzz_mode || scenario_mode || bench_mode {
+        relay_url = match args.next() {
+            Some(u) => u,
+            None => return usage(),
+        };
+    }
+
     let private_key = match args.next() {
         Some(s) => PrivateKey::try_from_bech32_string(&s)?,
         None => return usage(),
@@ -39,47 +141,173 @@ async fn main() -> Result<(), Error> {
     // post-static init of global variables
     Globals::init(relay_url, private_key).await?;
 
-    // deadlock detection thread
-    {
-        use parking_lot::deadlock;
-        use std::thread;
-        use std::time::Duration;
+    if fuzz_mode {
+        let seed: u64 = match args.next() {
+            Some(s) => match s.parse() {
+                Ok(seed) => seed,
+                Err(_) => return usage(),
+            },
+            None => rand::random(),
+        };
+        fuzz::run(seed, fuzz::DEFAULT_NUM_EVENTS, fuzz::DEFAULT_NUM_FILTERS).await?;
+        return GLOBALS
+            .connection
+            .write()
+            .as_mut()
+            .unwrap()
+            .disconnect()
+            .await;
+    }
 
-        thread::spawn(move || loop {
-            thread::sleep(Duration::from_secs(10));
-            let deadlocks = deadlock::check_deadlock();
-            if deadlocks.is_empty() {
-                continue;
-            }
+    if bench_mode {
+        let connections: usize = match args.next() {
+            Some(s) => match s.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return usage(),
+            },
+            None => bench::DEFAULT_CONNECTIONS,
+        };
+        let events_per_second: f64 = match args.next() {
+            Some(s) => match s.parse() {
+                Ok(n) if n > 0.0 && n.is_finite() => n,
+                _ => return usage(),
+            },
+            None => bench::DEFAULT_EVENTS_PER_SECOND,
+        };
+        let seconds: u64 = match args.next() {
+            Some(s) => match s.parse() {
+                Ok(n) if n > 0 => n,
+                _ => return usage(),
+            },
+            None => bench::DEFAULT_SECONDS,
+        };
+        return bench::run(connections, events_per_second, seconds).await;
+    }
 
-            println!("{} deadlocks detected", deadlocks.len());
-            for (i, threads) in deadlocks.iter().enumerate() {
-                println!("Deadlock #{}", i);
-                for t in threads {
-                    println!("Threadn Id {:#?}", t.thread_id());
-                    println!("{:#?}", t.backtrace());
-                }
-            }
-        });
+    if scenario_mode {
+        let paths: Vec<String> = args.collect();
+        if paths.is_empty() {
+            return usage();
+        }
+        return scenario::run_files(&paths).await;
+    }
+
+    spawn_deadlock_detection();
+
+    let stages: Vec<Stage> = Stage::iter().collect();
+    run_suite(&stages, &nips, flood, vanish).await?;
+    if let Some(path) = html {
+        report::write(&path, &[report::relay_section()])?;
+    }
+    Ok(())
+}
+
+// Test every relay in the config file, one after the other. The HTML report,
+// if asked for, covers all of them.
+async fn run_config(
+    path: &str,
+    nips: &[u32],
+    html: Option<&str>,
+    flood: bool,
+    vanish: bool,
+) -> Result<(), Error> {
+    let config = Config::load(path)?;
+    let flood = flood || config.flood;
+    let vanish = vanish || config.vanish;
+    *GLOBALS.timeouts.write() = config.timeouts;
+    *GLOBALS.required_overrides.write() = config.required_overrides()?;
+    let stages = config.enabled_stages()?;
+    let cli_profile = *GLOBALS.profile.read();
+
+    spawn_deadlock_detection();
+
+    let mut sections: Vec<String> = Vec::new();
+    for relay in config.relays.iter() {
+        let private_key = PrivateKey::try_from_bech32_string(&relay.nsec)?;
+        // A --profile on the command line overrides the config file
+        *GLOBALS.profile.write() = cli_profile.or(config.profile_for(relay)?);
+        Globals::init(relay.url.clone(), private_key).await?;
+
+        // Scenarios publish as the registered user, so they go before the
+        // suite's requests to vanish. The suite then starts over on a fresh
+        // connection, as its first stage expects.
+        if !config.scenarios.is_empty() {
+            scenario::run_files(&config.scenarios).await?;
+            let private_key = PrivateKey::try_from_bech32_string(&relay.nsec)?;
+            Globals::init(relay.url.clone(), private_key).await?;
+        }
+
+        run_suite(&stages, nips, flood, vanish).await?;
+        sections.push(report::relay_section());
     }
 
-    // Run the tests in stages
+    if let Some(path) = html {
+        report::write(path, &sections)?;
+    }
+
+    Ok(())
+}
+
+fn spawn_deadlock_detection() {
+    use parking_lot::deadlock;
+    use std::thread;
+    use std::time::Duration;
+
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(10));
+        let deadlocks = deadlock::check_deadlock();
+        if deadlocks.is_empty() {
+            continue;
+        }
+
+        println!("{} deadlocks detected", deadlocks.len());
+        for (i, threads) in deadlocks.iter().enumerate() {
+            println!("Deadlock #{}", i);
+            for t in threads {
+                println!("Threadn Id {:#?}", t.thread_id());
+                println!("{:#?}", t.backtrace());
+            }
+        }
+    });
+}
+
+// Run the tests of the given stages against the relay Globals::init()
+// connected to, then print the results. If any NIPs are given, only the
+// tests that reference one of them are run. Tests that flood the relay or
+// make the registered key vanish only run if asked for.
+async fn run_suite(stages: &[Stage], nips: &[u32], flood: bool, vanish: bool) -> Result<(), Error> {
+    let selected = |test_item: &TestItem| {
+        (nips.is_empty() || test_item.nips().iter().any(|n| nips.contains(n)))
+            && (flood || !test_item.floods())
+            && (vanish || !test_item.destructive())
+    };
+
     for stage in Stage::iter() {
-        eprintln!("-----------------------------------------------------");
-        eprintln!(
-            "*** Stage: {} ***",
-            format!("{:?}", stage).color(Color::Green3a)
-        );
+        let has_tests = TestItem::iter().any(|t| t.stage() == stage && selected(&t));
+        if !stages.contains(&stage) || !has_tests {
+            continue;
+        }
+        tracing::info!("Stage: {:?}", stage);
         stage.init().await?;
         for test_item in TestItem::iter() {
-            if test_item.stage() == stage {
-                eprintln!("  * TEST: {}", test_item.name());
+            if test_item.stage() == stage && selected(&test_item) {
+                tracing::info!("Test: {}", test_item.name());
 
-                let outcome = if stage == Stage::Unknown {
+                let started = std::time::Instant::now();
+                GLOBALS.transcript.write().start();
+                let mut outcome = if stage == Stage::Unknown {
                     Outcome::err("Test has not been assigned to a stage yet.".to_owned())
+                } else if let Some(prerequisite) = failed_prerequisite(test_item) {
+                    Outcome::err(format!(
+                        "Skipped: prerequisite {} did not pass",
+                        prerequisite.info().id
+                    ))
                 } else {
                     test_item.run().await
                 };
+                outcome.duration = Some(started.elapsed());
+                outcome.transcript = GLOBALS.transcript.write().finish();
+                print_transcript(&outcome);
 
                 GLOBALS.test_results.write().insert(test_item, outcome);
             }
@@ -95,9 +323,18 @@ async fn main() -> Result<(), Error> {
         .await?;
 
     // Display the results
-    eprintln!("====================================================");
     println!("SUMMARY RESULTS\n");
+    println!("Relay: {}\n", GLOBALS.relay_url.read());
+    let profile = *GLOBALS.profile.read();
+    if let Some(profile) = profile {
+        println!("Declared relay profile: {}\n", profile.name());
+    }
     for (test_item, outcome) in GLOBALS.test_results.read().iter() {
+        // Don't print the tests that were not selected to run
+        let duration = match outcome.duration {
+            Some(d) => d,
+            None => continue,
+        };
 
         // Don't print the tests that are not yet implemented
         if let Some(s) = &outcome.info {
@@ -106,20 +343,123 @@ async fn main() -> Result<(), Error> {
             }
         }
 
+        let display = match profile.and_then(|p| p.expected(*test_item)) {
+            Some(expected) => outcome.display_against_policy(expected),
+            None => outcome.display(required(*test_item)),
+        };
         println!(
-            "{}: {}",
+            "{}: {} [{}]",
             test_item.name(),
-            outcome.display(test_item.required())
+            display,
+            metrics::millis(duration)
+        );
+    }
+
+    // Conformant but slow is worth knowing about too
+    println!("\nLATENCY\n");
+    let durations: Vec<std::time::Duration> = GLOBALS
+        .test_results
+        .read()
+        .iter()
+        .filter(|(t, _)| t.info().implemented)
+        .filter_map(|(_, o)| o.duration)
+        .collect();
+    println!("test wall time: {}", metrics::summarize(&durations));
+    let latencies = GLOBALS.metrics.read();
+    for operation in Operation::iter() {
+        println!(
+            "{}: {}",
+            operation.name(),
+            metrics::summarize(latencies.samples(operation))
         );
     }
 
     Ok(())
 }
 
+// Only failures are worth reading through at the normal verbosity
+fn print_transcript(outcome: &Outcome) {
+    // At trace level every frame was already logged as it happened
+    if tracing::enabled!(tracing::Level::TRACE) {
+        return;
+    }
+
+    for frame in outcome.transcript.iter() {
+        let at_ms = frame.at.as_millis() as u64;
+        if outcome.pass == Some(true) {
+            tracing::debug!(
+                at_ms,
+                connection = %frame.connection,
+                direction = ?frame.direction,
+                "{}",
+                frame.text
+            );
+        } else {
+            tracing::info!(
+                at_ms,
+                connection = %frame.connection,
+                direction = ?frame.direction,
+                "{}",
+                frame.text
+            );
+        }
+    }
+}
+
+// Remove every occurrence of this flag, returning whether there was one
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| a != flag);
+    args.len() != before
+}
+
+// The first prerequisite of this test that ran and did not pass. Those that
+// did not run (e.g. because of --nip) are not held against it.
+fn failed_prerequisite(test_item: TestItem) -> Option<TestItem> {
+    let results = GLOBALS.test_results.read();
+    test_item
+        .info()
+        .prerequisites
+        .iter()
+        .find(|p| {
+            results
+                .get(*p)
+                .is_some_and(|o| o.duration.is_some() && o.pass != Some(true))
+        })
+        .copied()
+}
+
+// TestItem::required(), unless the config file overrides it
+fn required(test_item: TestItem) -> bool {
+    match GLOBALS.required_overrides.read().get(&test_item) {
+        Some(required) => *required,
+        None => test_item.required(),
+    }
+}
+
 fn usage() -> Result<(), Error> {
     eprintln!(
         "{}: relay-tester <relay_url> <allowed_nsec>",
         "Usage".color(Color::Gold1)
     );
+    eprintln!("       relay-tester --fuzz <relay_url> <allowed_nsec> [seed]");
+    eprintln!(
+        "       relay-tester --bench <relay_url> <allowed_nsec> [connections] [events/s] [seconds]"
+    );
+    eprintln!("       relay-tester --scenario <relay_url> <allowed_nsec> <scenarios.json>...");
+    eprintln!("       relay-tester --config <config.json>");
+    eprintln!("       relay-tester --list [--json]");
+    eprintln!(
+        "       add --profile <profile> to judge results against the relay's declared policy"
+    );
+    eprintln!("       add --quiet to print only the results, --verbose to print what went over");
+    eprintln!("       the wire for every test, or --trace to print every frame as it happens;");
+    eprintln!("       add --log-json to write the log as JSON");
+    eprintln!("       add --nip <number> (repeatable) to run only the tests of those NIPs");
+    eprintln!("       add --html <file> to also write the results as an HTML report");
+    eprintln!("       add --flood to also test how the relay throttles floods of requests");
+    eprintln!("       add --vanish to also test requests to vanish, which erase your key's events");
+    let names: Vec<&str> = Profile::iter().map(|p| p.name()).collect();
+    eprintln!("       profiles: {}", names.join(", "));
     Ok(())
 }
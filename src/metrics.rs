@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use strum_macros::EnumIter;
+
+/// Relay operations whose latency we measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum Operation {
+    // Opening the websocket
+    Connect,
+
+    // From connecting until the relay's first AUTH challenge
+    FirstAuthChallenge,
+
+    // From sending an EVENT until its OK
+    OkRoundTrip,
+
+    // From sending a REQ until its EOSE
+    TimeToEose,
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Operation::Connect => "connect",
+            Operation::FirstAuthChallenge => "first AUTH challenge",
+            Operation::OkRoundTrip => "OK round trip",
+            Operation::TimeToEose => "time to EOSE",
+        }
+    }
+}
+
+/// Latency samples for the relay being tested
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    samples: BTreeMap<Operation, Vec<Duration>>,
+}
+
+impl Metrics {
+    pub fn record(&mut self, operation: Operation, duration: Duration) {
+        self.samples.entry(operation).or_default().push(duration);
+    }
+
+    pub fn samples(&self, operation: Operation) -> &[Duration] {
+        self.samples
+            .get(&operation)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Count, 50th/90th/99th percentiles and maximum of these durations, or
+/// "no samples"
+pub fn summarize(samples: &[Duration]) -> String {
+    if samples.is_empty() {
+        return "no samples".to_owned();
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+
+    // Nearest-rank percentile
+    let percentile = |p: usize| {
+        let rank = (p * sorted.len()).div_ceil(100).max(1);
+        sorted[rank - 1]
+    };
+
+    format!(
+        "n={} p50={} p90={} p99={} max={}",
+        sorted.len(),
+        millis(percentile(50)),
+        millis(percentile(90)),
+        millis(percentile(99)),
+        millis(sorted[sorted.len() - 1])
+    )
+}
+
+pub fn millis(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
@@ -0,0 +1,288 @@
+use nostr_types::{Event, EventKind, Filter, Id, PublicKey, Unixtime};
+use std::collections::HashMap;
+
+/// A filter in a form the oracle can inspect and the fuzzer can shrink.
+/// It converts into a `nostr_types::Filter` for sending to the relay.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OracleFilter {
+    pub ids: Vec<Id>,
+    pub authors: Vec<PublicKey>,
+    pub kinds: Vec<EventKind>,
+    pub tags: Vec<(char, Vec<String>)>,
+    pub since: Option<Unixtime>,
+    pub until: Option<Unixtime>,
+    pub limit: Option<usize>,
+}
+
+impl OracleFilter {
+    pub fn to_filter(&self) -> Filter {
+        let mut filter = Filter::new();
+        filter.ids = self.ids.iter().map(|id| (*id).into()).collect();
+        filter.authors = self.authors.iter().map(|pk| (*pk).into()).collect();
+        filter.kinds = self.kinds.clone();
+        for (letter, values) in self.tags.iter() {
+            for value in values.iter() {
+                filter.add_tag_value(*letter, value.to_owned());
+            }
+        }
+        filter.since = self.since;
+        filter.until = self.until;
+        filter.limit = self.limit;
+        filter
+    }
+
+    // NIP-01 filter matching, ignoring limit
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.ids.is_empty() && !self.ids.contains(&event.id) {
+            return false;
+        }
+        if !self.authors.is_empty() && !self.authors.contains(&event.pubkey) {
+            return false;
+        }
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+        for (letter, values) in self.tags.iter() {
+            let name = letter.to_string();
+            if !event
+                .tags
+                .iter()
+                .any(|t| t.tagname() == name && values.iter().any(|v| v == t.value()))
+            {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if event.created_at.0 < since.0 {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if event.created_at.0 > until.0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Every filter that is this one with a single constraint removed.
+    /// Filters always keep either an ids or an authors constraint so that
+    /// they cannot match events which the oracle never saw.
+    pub fn reductions(&self) -> Vec<OracleFilter> {
+        let mut output: Vec<OracleFilter> = Vec::new();
+
+        if !self.ids.is_empty() && !self.authors.is_empty() {
+            let mut f = self.clone();
+            f.ids.clear();
+            output.push(f);
+            let mut f = self.clone();
+            f.authors.clear();
+            output.push(f);
+        }
+        if self.ids.len() > 1 {
+            for i in 0..self.ids.len() {
+                let mut f = self.clone();
+                f.ids.remove(i);
+                output.push(f);
+            }
+        }
+        if self.authors.len() > 1 {
+            for i in 0..self.authors.len() {
+                let mut f = self.clone();
+                f.authors.remove(i);
+                output.push(f);
+            }
+        }
+        if !self.kinds.is_empty() {
+            let mut f = self.clone();
+            f.kinds.clear();
+            output.push(f);
+        }
+        if self.kinds.len() > 1 {
+            for i in 0..self.kinds.len() {
+                let mut f = self.clone();
+                f.kinds.remove(i);
+                output.push(f);
+            }
+        }
+        for i in 0..self.tags.len() {
+            let mut f = self.clone();
+            f.tags.remove(i);
+            output.push(f);
+            if self.tags[i].1.len() > 1 {
+                for j in 0..self.tags[i].1.len() {
+                    let mut f = self.clone();
+                    f.tags[i].1.remove(j);
+                    output.push(f);
+                }
+            }
+        }
+        if self.since.is_some() {
+            let mut f = self.clone();
+            f.since = None;
+            output.push(f);
+        }
+        if self.until.is_some() {
+            let mut f = self.clone();
+            f.until = None;
+            output.push(f);
+        }
+        if self.limit.is_some() {
+            let mut f = self.clone();
+            f.limit = None;
+            output.push(f);
+        }
+
+        output
+    }
+}
+
+/// An in-memory model of what a NIP-01 relay should be storing, including
+/// replaceable and addressable event replacement and NIP-09 deletion.
+#[derive(Debug, Default)]
+pub struct Oracle {
+    events: Vec<Event>,
+
+    // Deleted event id (hex) -> the pubkey that deleted it
+    deleted_ids: HashMap<String, PublicKey>,
+
+    // Deleted address -> created_at of the newest deletion of it
+    deleted_addrs: HashMap<String, Unixtime>,
+}
+
+impl Oracle {
+    pub fn new() -> Oracle {
+        Default::default()
+    }
+
+    pub fn insert(&mut self, event: &Event) {
+        let kind: u32 = event.kind.into();
+
+        if is_ephemeral(kind) {
+            return;
+        }
+
+        let id_hex = event.id.as_hex_string();
+        if self.events.iter().any(|e| e.id == event.id) {
+            return;
+        }
+        if self.deleted_ids.get(&id_hex) == Some(&event.pubkey) {
+            return;
+        }
+
+        if is_replaceable(kind) || is_addressable(kind) {
+            let addr = address_of(event);
+            if let Some(deleted_at) = self.deleted_addrs.get(&addr) {
+                if event.created_at.0 <= deleted_at.0 {
+                    return;
+                }
+            }
+            if let Some(i) = self.events.iter().position(|e| address_of(e) == addr) {
+                if supersedes(&self.events[i], event) {
+                    return;
+                }
+                self.events.remove(i);
+            }
+        }
+
+        if kind == 5 {
+            self.apply_deletion(event);
+        }
+
+        self.events.push(event.clone());
+    }
+
+    fn apply_deletion(&mut self, deletion: &Event) {
+        for tag in deletion.tags.iter() {
+            match tag.tagname() {
+                "e" => {
+                    let target = tag.value().to_owned();
+                    self.events.retain(|e| {
+                        let k: u32 = e.kind.into();
+                        !(e.id.as_hex_string() == target && e.pubkey == deletion.pubkey && k != 5)
+                    });
+                    self.deleted_ids.insert(target, deletion.pubkey);
+                }
+                "a" => {
+                    let addr = tag.value().to_owned();
+                    let mut parts = addr.splitn(3, ':');
+                    let _kind = parts.next();
+                    if parts.next() != Some(deletion.pubkey.as_hex_string().as_str()) {
+                        continue;
+                    }
+                    self.events.retain(|e| {
+                        let k: u32 = e.kind.into();
+                        !((is_replaceable(k) || is_addressable(k))
+                            && address_of(e) == addr
+                            && e.created_at.0 <= deletion.created_at.0)
+                    });
+                    let entry = self
+                        .deleted_addrs
+                        .entry(addr)
+                        .or_insert(deletion.created_at);
+                    if deletion.created_at.0 > entry.0 {
+                        *entry = deletion.created_at;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The events a relay should return for this filter, newest first.
+    pub fn query(&self, filter: &OracleFilter) -> Vec<Event> {
+        let mut output: Vec<Event> = self
+            .events
+            .iter()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect();
+        output.sort_by(|a, b| {
+            b.created_at
+                .0
+                .cmp(&a.created_at.0)
+                .then_with(|| a.id.as_hex_string().cmp(&b.id.as_hex_string()))
+        });
+        if let Some(limit) = filter.limit {
+            output.truncate(limit);
+        }
+        output
+    }
+}
+
+pub fn is_replaceable(kind: u32) -> bool {
+    kind == 0 || kind == 3 || (10000..20000).contains(&kind)
+}
+
+pub fn is_ephemeral(kind: u32) -> bool {
+    (20000..30000).contains(&kind)
+}
+
+pub fn is_addressable(kind: u32) -> bool {
+    (30000..40000).contains(&kind)
+}
+
+// The replaceable address of an event ("kind:pubkey:d"). For non-addressable
+// events the d-tag part is empty.
+pub fn address_of(event: &Event) -> String {
+    let kind: u32 = event.kind.into();
+    let d = if is_addressable(kind) {
+        event
+            .tags
+            .iter()
+            .find(|t| t.tagname() == "d")
+            .map(|t| t.value().to_owned())
+            .unwrap_or_default()
+    } else {
+        "".to_owned()
+    };
+    format!("{}:{}:{}", kind, event.pubkey.as_hex_string(), d)
+}
+
+// Whether the existing event wins over the candidate for the same address.
+// Ties on created_at go to the lowest id.
+fn supersedes(existing: &Event, candidate: &Event) -> bool {
+    existing.created_at.0 > candidate.created_at.0
+        || (existing.created_at.0 == candidate.created_at.0
+            && existing.id.as_hex_string() < candidate.id.as_hex_string())
+}
@@ -1,9 +1,17 @@
+use crate::transcript::Frame;
 use colorful::{Color, Colorful};
+use std::time::Duration;
 
 #[derive(Debug, Default, Clone)]
 pub struct Outcome {
     pub pass: Option<bool>,
     pub info: Option<String>,
+
+    // Wall time the test took, filled in by the runner
+    pub duration: Option<Duration>,
+
+    // What went over the wire during the test, filled in by the runner
+    pub transcript: Vec<Frame>,
 }
 
 impl Outcome {
@@ -11,6 +19,8 @@ impl Outcome {
         Outcome {
             pass: Some(true),
             info,
+            duration: None,
+            transcript: Vec::new(),
         }
     }
 
@@ -18,6 +28,8 @@ impl Outcome {
         Outcome {
             pass: Some(false),
             info,
+            duration: None,
+            transcript: Vec::new(),
         }
     }
 
@@ -25,6 +37,8 @@ impl Outcome {
         Outcome {
             pass: None,
             info: Some(info),
+            duration: None,
+            transcript: Vec::new(),
         }
     }
 }
@@ -63,3 +77,29 @@ impl Outcome {
         }
     }
 }
+
+impl Outcome {
+    // Display against what the declared relay profile expects, instead of
+    // against required()
+    pub fn display_against_policy(&self, expected: bool) -> String {
+        let yes_no = |b: bool| if b { "YES" } else { "NO" };
+        let verdict = match self.pass {
+            None => return self.display(false),
+            Some(pass) if pass == expected => format!(
+                "{} ({}, conforms to declared policy)",
+                "CONFORMS".color(Color::Green),
+                yes_no(pass)
+            ),
+            Some(pass) => format!(
+                "{} ({}, declared policy expects {})",
+                "VIOLATES".color(Color::Red3a),
+                yes_no(pass),
+                yes_no(expected)
+            ),
+        };
+        match self.info {
+            None => verdict,
+            Some(ref s) => format!("{} ({})", verdict, s),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::test_item::TestItem;
+use strum_macros::EnumIter;
+
+/// The access policy the relay operator says the relay implements. Tests whose
+/// outcome depends on policy are then judged against what the profile
+/// expects, rather than simply reported as YES or NO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Profile {
+    // Anyone can read and write
+    Public,
+
+    // Anyone can read, only paying (registered) users can write
+    Paid,
+
+    // Only whitelisted (registered) users can write anything at all
+    WhitelistOnly,
+
+    // Nobody can write through the websocket
+    ReadOnly,
+
+    // A DM inbox: anyone can send DMs in, only the recipient can read them
+    DmOnly,
+
+    // Members only, both for reading and for writing
+    Community,
+}
+
+impl Profile {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Profile::Public => "public",
+            Profile::Paid => "paid",
+            Profile::WhitelistOnly => "whitelist-only",
+            Profile::ReadOnly => "read-only",
+            Profile::DmOnly => "dm-only",
+            Profile::Community => "community",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Profile> {
+        use strum::IntoEnumIterator;
+        Profile::iter().find(|p| p.name() == name)
+    }
+
+    /// Whether a relay following this policy should pass this test, or None if
+    /// the policy says nothing about it.
+    ///
+    /// Many of the tests mapped here are still tbd() and never run, so their
+    /// expectations are not judged yet. For now a profile is only checked
+    /// against PublicCanWrite, AcceptsRelayListsFromPublic,
+    /// AcceptsDmRelayListsFromPublic, AcceptsEphemeralEventsFromPublic and
+    /// (for Community) PromptsForAuthInitially.
+    pub fn expected(&self, test_item: TestItem) -> Option<bool> {
+        use TestItem::*;
+
+        match *self {
+            Profile::Public => match test_item {
+                PublicCanWrite => Some(true),
+                AcceptsRelayListsFromPublic => Some(true),
+                AcceptsDmRelayListsFromPublic => Some(true),
+                AcceptsEphemeralEventsFromPublic => Some(true),
+                UnknownCanWriteOwn => Some(true),
+                UnknownCanReadbackOwn => Some(true),
+                UnknownCanReadbackOther => Some(true),
+                _ => None,
+            },
+            Profile::Paid => match test_item {
+                PublicCanWrite => Some(false),
+                UnknownCanWriteOwn => Some(false),
+                UnknownCanWriteOther => Some(false),
+                UnknownCanReadbackOther => Some(true),
+                KnownCanWriteOwn => Some(true),
+                _ => None,
+            },
+            Profile::WhitelistOnly => match test_item {
+                PublicCanWrite => Some(false),
+                AcceptsRelayListsFromPublic => Some(false),
+                AcceptsDmRelayListsFromPublic => Some(false),
+                AcceptsEphemeralEventsFromPublic => Some(false),
+                UnknownCanWriteOwn => Some(false),
+                UnknownCanWriteOther => Some(false),
+                KnownCanWriteOwn => Some(true),
+                _ => None,
+            },
+            Profile::ReadOnly => match test_item {
+                PublicCanWrite => Some(false),
+                AcceptsRelayListsFromPublic => Some(false),
+                AcceptsDmRelayListsFromPublic => Some(false),
+                AcceptsEphemeralEventsFromPublic => Some(false),
+                UnknownCanWriteOwn => Some(false),
+                UnknownCanWriteOther => Some(false),
+                KnownCanWriteOwn => Some(false),
+                KnownCanWriteOther => Some(false),
+                _ => None,
+            },
+            Profile::DmOnly => match test_item {
+                PublicCanWrite => Some(false),
+                AcceptsRelayListsFromPublic => Some(false),
+                AcceptsEphemeralEventsFromPublic => Some(false),
+                Nip4DmsRequireAuth => Some(true),
+                GiftwrapsRequireAuth => Some(true),
+                UnknownCanReadbackOther => Some(false),
+                _ => None,
+            },
+            Profile::Community => match test_item {
+                PromptsForAuthInitially => Some(true),
+                PublicCanWrite => Some(false),
+                AcceptsRelayListsFromPublic => Some(false),
+                AcceptsDmRelayListsFromPublic => Some(false),
+                AcceptsEphemeralEventsFromPublic => Some(false),
+                UnknownCanWriteOwn => Some(false),
+                UnknownCanWriteOther => Some(false),
+                UnknownCanReadbackOther => Some(false),
+                KnownCanWriteOwn => Some(true),
+                KnownCanReadbackOther => Some(true),
+                _ => None,
+            },
+        }
+    }
+}
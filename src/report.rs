@@ -0,0 +1,325 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::metrics::{self, Operation};
+use crate::outcome::Outcome;
+use crate::stage::Stage;
+use crate::test_item::TestItem;
+use crate::transcript::Direction;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use strum::IntoEnumIterator;
+
+// The colors Outcome::display() prints in
+const GREY: &str = "#7f7f7f";
+const DARK_GOLDENROD: &str = "#af8700";
+const GREEN: &str = "#008000";
+const RED: &str = "#af0000";
+
+// What we show of the NIP-11 document above the full dump
+const NIP11_FIELDS: [&str; 6] = [
+    "name",
+    "description",
+    "software",
+    "version",
+    "contact",
+    "pubkey",
+];
+
+const STYLE: &str = "
+body { font-family: sans-serif; max-width: 72em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; vertical-align: top; }
+.badge { color: white; font-weight: bold; padding: 0.1em 0.5em; border-radius: 0.3em; }
+details { margin: 0.3em 0; }
+summary { cursor: pointer; }
+pre, .frame { font-family: monospace; white-space: pre-wrap; word-break: break-all; }
+.muted { color: #7f7f7f; }
+";
+
+/// One relay's results as HTML. Take it right after run_suite(), since the
+/// next Globals::init() forgets them.
+pub fn relay_section() -> String {
+    let mut html = String::new();
+    let relay_url = GLOBALS.relay_url.read().clone();
+
+    let _ = writeln!(html, "<section>\n<h2>{}</h2>", escape(&relay_url));
+    metadata(&mut html, &relay_url);
+    stage_summary(&mut html);
+
+    html.push_str("<h3>Tests</h3>\n");
+    for stage in Stage::iter() {
+        let results = reported(stage);
+        if results.is_empty() {
+            continue;
+        }
+        let _ = writeln!(html, "<h4>{:?}</h4>", stage);
+        for (test_item, outcome) in results.iter() {
+            test_details(&mut html, *test_item, outcome);
+        }
+    }
+
+    latency(&mut html);
+    nip11(&mut html);
+    html.push_str("</section>\n");
+    html
+}
+
+/// Write a self-contained HTML page with these relay sections
+pub fn write(path: &str, sections: &[String]) -> Result<(), Error> {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Relay conformance report</title>\n");
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+    html.push_str("<h1>Relay conformance report</h1>\n");
+    let _ = writeln!(
+        html,
+        "<p class=\"muted\">Generated by relay-tester {} at {}</p>",
+        env!("CARGO_PKG_VERSION"),
+        now_utc()
+    );
+    for section in sections.iter() {
+        html.push_str(section);
+    }
+    html.push_str("</body>\n</html>\n");
+
+    std::fs::write(path, html)?;
+    tracing::info!("Wrote HTML report to {}", path);
+    Ok(())
+}
+
+fn metadata(html: &mut String, relay_url: &str) {
+    html.push_str("<table>\n");
+    row(html, "Relay", relay_url);
+    if let Some(profile) = *GLOBALS.profile.read() {
+        row(html, "Declared profile", profile.name());
+    }
+    if let Some(nip11) = GLOBALS.nip11.read().as_ref() {
+        for field in NIP11_FIELDS {
+            if let Some(value) = nip11.get(field).and_then(|v| v.as_str()) {
+                row(html, field, value);
+            }
+        }
+        if let Some(nips) = nip11.get("supported_nips").and_then(|v| v.as_array()) {
+            let nips: Vec<String> = nips.iter().map(|n| n.to_string()).collect();
+            row(html, "supported_nips", &nips.join(", "));
+        }
+    }
+    html.push_str("</table>\n");
+}
+
+fn row(html: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        html,
+        "<tr><th>{}</th><td>{}</td></tr>",
+        escape(key),
+        escape(value)
+    );
+}
+
+// How many tests of each stage got each color of badge
+fn stage_summary(html: &mut String) {
+    html.push_str("<h3>Summary</h3>\n<table>\n");
+    html.push_str("<tr><th>Stage</th><th>Tests</th>");
+    for (label, color) in [
+        ("passed", GREEN),
+        ("failed", RED),
+        ("no", DARK_GOLDENROD),
+        ("untested", GREY),
+    ] {
+        let _ = write!(html, "<th>{}</th>", badge_html(label, color));
+    }
+    html.push_str("</tr>\n");
+
+    for stage in Stage::iter() {
+        let results = reported(stage);
+        if results.is_empty() {
+            continue;
+        }
+        let count = |color: &str| {
+            results
+                .iter()
+                .filter(|(t, o)| badge(*t, o).1 == color)
+                .count()
+        };
+        let _ = writeln!(
+            html,
+            "<tr><td>{:?}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            stage,
+            results.len(),
+            count(GREEN),
+            count(RED),
+            count(DARK_GOLDENROD),
+            count(GREY)
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+// A collapsible entry with the test's details and what went over the wire
+fn test_details(html: &mut String, test_item: TestItem, outcome: &Outcome) {
+    let info = test_item.info();
+    let (label, color) = badge(test_item, outcome);
+    let duration = outcome.duration.map(metrics::millis).unwrap_or_default();
+
+    let _ = writeln!(
+        html,
+        "<details>\n<summary>{} {} <span class=\"muted\">{}</span></summary>",
+        badge_html(label, color),
+        escape(info.name),
+        duration
+    );
+
+    html.push_str("<table>\n");
+    row(html, "Test", info.id);
+    let nips: Vec<String> = info.nips.iter().map(|n| format!("NIP-{:02}", n)).collect();
+    row(html, "NIPs", &nips.join(", "));
+    let yes_no = |b: bool| if b { "YES" } else { "NO" };
+    row(html, "Required", yes_no(crate::required(test_item)));
+    let profile = *GLOBALS.profile.read();
+    if let Some(expected) = profile.and_then(|p| p.expected(test_item)) {
+        row(html, "Declared policy expects", yes_no(expected));
+    }
+    if let Some(s) = &outcome.info {
+        row(html, "Info", s);
+    }
+    html.push_str("</table>\n");
+
+    if outcome.transcript.is_empty() {
+        html.push_str("<p class=\"muted\">Nothing went over the wire.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>ms</th><th>Connection</th><th></th><th>Frame</th></tr>\n");
+        for frame in outcome.transcript.iter() {
+            let arrow = match frame.direction {
+                Direction::Sent => "&rarr;",
+                Direction::Received => "&larr;",
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"frame\">{}</td></tr>",
+                frame.at.as_millis(),
+                escape(&frame.connection),
+                arrow,
+                escape(&frame.text)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</details>\n");
+}
+
+fn latency(html: &mut String) {
+    html.push_str("<h3>Latency</h3>\n<table>\n");
+    let durations: Vec<Duration> = GLOBALS
+        .test_results
+        .read()
+        .iter()
+        .filter(|(t, _)| t.info().implemented)
+        .filter_map(|(_, o)| o.duration)
+        .collect();
+    row(html, "test wall time", &metrics::summarize(&durations));
+    let latencies = GLOBALS.metrics.read();
+    for operation in Operation::iter() {
+        row(
+            html,
+            operation.name(),
+            &metrics::summarize(latencies.samples(operation)),
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn nip11(html: &mut String) {
+    html.push_str("<h3>NIP-11 document</h3>\n");
+    match GLOBALS.nip11.read().as_ref() {
+        Some(nip11) => {
+            let pretty = serde_json::to_string_pretty(nip11).unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<details>\n<summary>Show</summary>\n<pre>{}</pre>\n</details>",
+                escape(&pretty)
+            );
+        }
+        None => html.push_str("<p class=\"muted\">The relay did not serve one.</p>\n"),
+    }
+}
+
+// The tests of this stage that ran. Like the summary printed to the
+// terminal, this leaves out tests that are not yet implemented.
+fn reported(stage: Stage) -> Vec<(TestItem, Outcome)> {
+    GLOBALS
+        .test_results
+        .read()
+        .iter()
+        .filter(|(t, o)| t.stage() == stage && t.info().implemented && o.duration.is_some())
+        .map(|(t, o)| (*t, o.clone()))
+        .collect()
+}
+
+// The same verdict and color as Outcome::display() and
+// Outcome::display_against_policy()
+fn badge(test_item: TestItem, outcome: &Outcome) -> (&'static str, &'static str) {
+    let profile = *GLOBALS.profile.read();
+    let expected = profile.and_then(|p| p.expected(test_item));
+    match (outcome.pass, expected) {
+        (None, _) => ("UNTESTED", GREY),
+        (Some(pass), Some(expected)) if pass == expected => ("CONFORMS", GREEN),
+        (Some(_), Some(_)) => ("VIOLATES", RED),
+        (Some(true), None) if crate::required(test_item) => ("PASS", GREEN),
+        (Some(true), None) => ("YES", GREEN),
+        (Some(false), None) if crate::required(test_item) => ("FAIL", RED),
+        (Some(false), None) => ("NO", DARK_GOLDENROD),
+    }
+}
+
+fn badge_html(label: &str, color: &str) -> String {
+    format!(
+        "<span class=\"badge\" style=\"background: {}\">{}</span>",
+        color, label
+    )
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// e.g. "2024-06-01 12:34:56 UTC", without pulling in a date crate
+fn now_utc() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rest) = (secs / 86400, secs % 86400);
+
+    // Howard Hinnant's civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
@@ -0,0 +1,271 @@
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use colorful::{Color, Colorful};
+use nostr_types::{Event, EventKind, Signer, Tag, Unixtime};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// A relay-specific regression case, written as JSON so it can be added
+/// without recompiling. A scenario file holds an array of these.
+///
+/// Strings in tags and filters may use these placeholders:
+///   "$registered", "$stranger"  the hex public key of that user
+///   "$id:<name>"                the hex id of an event published earlier
+///   "$ago:<seconds>"            (filters only, as a whole value) a timestamp
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    Publish(PublishStep),
+    Query(QueryStep),
+
+    // Seconds
+    Sleep(u64),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PublishStep {
+    // How later steps refer to this event
+    pub name: String,
+
+    pub kind: u32,
+
+    #[serde(default)]
+    pub tags: Vec<Vec<String>>,
+
+    #[serde(default)]
+    pub content: String,
+
+    #[serde(default)]
+    pub seconds_ago: i64,
+
+    // Publish as the stranger instead of the registered user
+    #[serde(default)]
+    pub stranger: bool,
+
+    // The OK status we expect. Either is fine if absent.
+    #[serde(default)]
+    pub ok: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryStep {
+    pub filters: Vec<Value>,
+
+    // Names of the events expected back. Events that this scenario did not
+    // publish are ignored.
+    pub expect: Vec<String>,
+
+    // Whether the events must come back in exactly the expected order
+    #[serde(default)]
+    pub ordered: bool,
+}
+
+pub fn load(path: &str) -> Result<Vec<Scenario>, Error> {
+    let contents = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Run every scenario in these files against the relay Globals::init()
+/// connected to, as the registered user, and print the results
+pub async fn run_files(paths: &[String]) -> Result<(), Error> {
+    let mut scenarios: Vec<Scenario> = Vec::new();
+    for path in paths.iter() {
+        scenarios.extend(load(path)?);
+    }
+
+    {
+        let mut lock = GLOBALS.connection.write();
+        let connection = lock.as_mut().unwrap();
+        if connection.disconnected {
+            connection.reconnect().await?;
+        }
+        let _ = connection.authenticate(true, wait()).await?;
+    }
+
+    let mut results: Vec<(String, Outcome)> = Vec::new();
+    for scenario in scenarios.iter() {
+        tracing::info!("Scenario: {}", scenario.name);
+        GLOBALS.transcript.write().start();
+        let mut outcome = match run(scenario).await {
+            Ok(outcome) => outcome,
+            Err(e) => Outcome::err(format!("{}", e)),
+        };
+        outcome.transcript = GLOBALS.transcript.write().finish();
+        crate::print_transcript(&outcome);
+        results.push((scenario.name.clone(), outcome));
+    }
+
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .disconnect()
+        .await?;
+
+    println!("SCENARIO RESULTS\n");
+    for (name, outcome) in results.iter() {
+        println!(
+            "{}: {}",
+            name.clone().color(Color::Cyan),
+            outcome.display(true)
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn run(scenario: &Scenario) -> Result<Outcome, Error> {
+    let mut published: Vec<(String, Event)> = Vec::new();
+
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let step_number = i + 1;
+        match step {
+            Step::Publish(publish) => {
+                let mut tags: Vec<Tag> = Vec::new();
+                for tag in publish.tags.iter() {
+                    let mut values: Vec<String> = Vec::new();
+                    for value in tag.iter() {
+                        match substitute(value, &published) {
+                            Ok(v) => values.push(v),
+                            Err(e) => return Ok(Outcome::err(format!("step {step_number}: {e}"))),
+                        }
+                    }
+                    let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+                    tags.push(Tag::new(&values));
+                }
+
+                let event = Globals::make_event(
+                    EventParts::Dated(
+                        EventKind::from(publish.kind),
+                        tags,
+                        publish.content.clone(),
+                        Unixtime(Unixtime::now().0 - publish.seconds_ago),
+                    ),
+                    !publish.stranger,
+                )?;
+
+                let (ok, reason) = GLOBALS
+                    .connection
+                    .write()
+                    .as_mut()
+                    .unwrap()
+                    .post_event(event.clone(), wait())
+                    .await?;
+
+                if let Some(expected) = publish.ok {
+                    if ok != expected {
+                        return Ok(Outcome::fail(Some(format!(
+                            "step {}: publishing {} got OK {} ({}), expected {}",
+                            step_number, publish.name, ok, reason, expected
+                        ))));
+                    }
+                }
+
+                published.push((publish.name.clone(), event));
+            }
+            Step::Query(query) => {
+                let mut filters: Vec<String> = Vec::new();
+                for filter in query.filters.iter() {
+                    match substitute_value(filter, &published) {
+                        Ok(f) => filters.push(serde_json::to_string(&f)?),
+                        Err(e) => return Ok(Outcome::err(format!("step {step_number}: {e}"))),
+                    }
+                }
+                let filters: Vec<&str> = filters.iter().map(|f| f.as_str()).collect();
+
+                let events = GLOBALS
+                    .connection
+                    .write()
+                    .as_mut()
+                    .unwrap()
+                    .fetch_events_raw(&filters, wait())
+                    .await?
+                    .into_events();
+
+                // Only the events this scenario published count
+                let mut got: Vec<String> = events
+                    .iter()
+                    .filter_map(|e| {
+                        published
+                            .iter()
+                            .find(|(_, p)| p.id == e.id)
+                            .map(|(name, _)| name.clone())
+                    })
+                    .collect();
+                let mut expected = query.expect.clone();
+                if !query.ordered {
+                    got.sort();
+                    expected.sort();
+                }
+
+                if got != expected {
+                    return Ok(Outcome::fail(Some(format!(
+                        "step {}: expected {:?}, got {:?}",
+                        step_number, expected, got
+                    ))));
+                }
+            }
+            Step::Sleep(seconds) => {
+                tokio::time::sleep(Duration::from_secs(*seconds)).await;
+            }
+        }
+    }
+
+    Ok(Outcome::pass(None))
+}
+
+fn substitute(s: &str, published: &[(String, Event)]) -> Result<String, String> {
+    if s == "$registered" {
+        return Ok(GLOBALS.registered_user.read().public_key().as_hex_string());
+    }
+    if s == "$stranger" {
+        return Ok(GLOBALS.stranger.read().public_key().as_hex_string());
+    }
+    if let Some(name) = s.strip_prefix("$id:") {
+        return match published.iter().find(|(n, _)| n == name) {
+            Some((_, event)) => Ok(event.id.as_hex_string()),
+            None => Err(format!("no event named {} has been published", name)),
+        };
+    }
+    Ok(s.to_owned())
+}
+
+fn substitute_value(value: &Value, published: &[(String, Event)]) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => match s.strip_prefix("$ago:") {
+            Some(seconds) => {
+                let seconds: i64 = seconds
+                    .parse()
+                    .map_err(|_| format!("bad placeholder {}", s))?;
+                Value::from(Unixtime::now().0 - seconds)
+            }
+            None => Value::String(substitute(s, published)?),
+        },
+        Value::Array(a) => Value::Array(
+            a.iter()
+                .map(|v| substitute_value(v, published))
+                .collect::<Result<Vec<Value>, String>>()?,
+        ),
+        Value::Object(o) => {
+            let mut output = serde_json::Map::new();
+            for (k, v) in o.iter() {
+                output.insert(k.clone(), substitute_value(v, published)?);
+            }
+            Value::Object(output)
+        }
+        v => v.clone(),
+    })
+}
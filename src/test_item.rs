@@ -1,738 +1,1234 @@
 use crate::error::Error;
 use crate::outcome::Outcome;
 use crate::stage::Stage;
+use crate::tests::{
+    auth, delete, eose, ephemeral, filters, find, json, large, misc_events, nip11, protected,
+    public, rate_limit, reg, replaceables, tbd, time, unicode, validation, vanish,
+};
 use strum_macros::{EnumCount, EnumIter};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumCount, EnumIter)]
-#[repr(usize)]
-pub enum TestItem {
+/// Everything known about a test other than how to run it
+#[derive(Debug, Clone, Copy)]
+pub struct TestInfo {
+    // The Rust identifier, as used in config files
+    pub id: &'static str,
+
+    pub name: &'static str,
+
+    // The NIPs whose behaviour the test checks
+    pub nips: &'static [u32],
+
+    pub stage: Stage,
+
+    pub required: bool,
+
+    // Free-form labels such as "slow" or "destructive"
+    pub tags: &'static [&'static str],
+
+    // Tests that must pass for this one to be meaningful. If one of them ran
+    // and did not pass, this one is skipped.
+    pub prerequisites: &'static [TestItem],
+
+    // False while the test is still a tbd() placeholder
+    pub implemented: bool,
+}
+
+// Declares TestItem together with the TestInfo and run function of each
+// test, so that adding a test means touching only one place.
+macro_rules! test_items {
+    ($(
+        $item:ident {
+            name: $name:literal,
+            nips: [$($nip:literal),*],
+            stage: $stage:ident,
+            required: $required:literal,
+            $(tags: [$($tag:literal),*],)?
+            $(prerequisites: [$($prerequisite:ident),*],)?
+            run: $run:expr,
+        },
+    )*) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumCount, EnumIter)]
+        #[repr(usize)]
+        pub enum TestItem {
+            $($item,)*
+        }
+
+        impl TestItem {
+            pub fn info(&self) -> TestInfo {
+                match *self {
+                    $(TestItem::$item => TestInfo {
+                        id: stringify!($item),
+                        name: $name,
+                        nips: &[$($nip),*],
+                        stage: Stage::$stage,
+                        required: $required,
+                        tags: &[$($($tag),*)?],
+                        prerequisites: &[$($(TestItem::$prerequisite),*)?],
+                        implemented: stringify!($run) != "tbd()",
+                    },)*
+                }
+            }
+
+            async fn run_inner(&self) -> Result<Outcome, Error> {
+                match *self {
+                    $(TestItem::$item => $run.await,)*
+                }
+            }
+        }
+    };
+}
+
+// Tests of a stage run in the order they are listed here
+test_items! {
     // Pre-Auth: nip11
-    Nip11Provided,
-    ClaimsSupportForNip4,
-    ClaimsSupportForNip9,
-    ClaimsSupportForNip11,
-    ClaimsSupportForNip26,
-    ClaimsSupportForNip29,
-    ClaimsSupportForNip40,
-    ClaimsSupportForNip42,
-    ClaimsSupportForNip45,
-    ClaimsSupportForNip50,
-    ClaimsSupportForNip59,
-    ClaimsSupportForNip65,
-    ClaimsSupportForNip94,
-    ClaimsSupportForNip96,
+    Nip11Provided {
+        name: "NIP-11 document is provided",
+        nips: [11],
+        stage: Preauth,
+        required: false,
+        run: nip11::nip11_provided(),
+    },
+    ClaimsSupportForNip4 {
+        name: "Claims support for NIP-04 (old DMs)",
+        nips: [11, 4],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(4),
+    },
+    ClaimsSupportForNip9 {
+        name: "Claims support for NIP-09 (Deletion)",
+        nips: [11, 9],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(9),
+    },
+    ClaimsSupportForNip11 {
+        name: "Claims support for NIP-11 (Relay Information Document)",
+        nips: [11],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(11),
+    },
+    ClaimsSupportForNip26 {
+        name: "Claims support for NIP-26 (Delegated Event Signing)",
+        nips: [11, 26],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(26),
+    },
+    ClaimsSupportForNip29 {
+        name: "Claims support for NIP-29 (Relay-based Groups)",
+        nips: [11, 29],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(29),
+    },
+    ClaimsSupportForNip40 {
+        name: "Claims support for NIP-40 (Expiration Timestamp)",
+        nips: [11, 40],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(40),
+    },
+    ClaimsSupportForNip42 {
+        name: "Claims support for NIP-42 (AUTH)",
+        nips: [11, 42],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(42),
+    },
+    ClaimsSupportForNip45 {
+        name: "Claims support for NIP-45 (COUNT)",
+        nips: [11, 45],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(45),
+    },
+    ClaimsSupportForNip50 {
+        name: "Claims support for NIP-50 (SEARCH)",
+        nips: [11, 50],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(50),
+    },
+    ClaimsSupportForNip59 {
+        name: "Claims support for NIP-59 (Giftwrap)",
+        nips: [11, 59],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(59),
+    },
+    ClaimsSupportForNip65 {
+        name: "Claims support for NIP-65 (Relay Lists)",
+        nips: [11, 65],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(65),
+    },
+    ClaimsSupportForNip94 {
+        name: "Claims support for NIP-94 (File Metadata)",
+        nips: [11, 94],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(94),
+    },
+    ClaimsSupportForNip96 {
+        name: "Claims support for NIP-96 (HTTP file storage)",
+        nips: [11, 96],
+        stage: Preauth,
+        required: false,
+        run: nip11::claimed_support_for_nip(96),
+    },
 
     // Pre-Auth: auth
-    PromptsForAuthInitially,
+    PromptsForAuthInitially {
+        name: "Prompts for AUTH when client connects",
+        nips: [42],
+        stage: Preauth,
+        required: false,
+        run: auth::prompts_for_auth_initially(),
+    },
 
     // Pre-Auth: eose
-    SupportsEose,
-    ClosesCompleteSubscriptionsAfterEose,
-    KeepsOpenIncompleteSubscriptionsAfterEose,
+    SupportsEose {
+        name: "Supports EOSE",
+        nips: [1],
+        stage: Preauth,
+        required: true,
+        run: eose::supports_eose(),
+    },
+    ClosesCompleteSubscriptionsAfterEose {
+        name: "Closes complete subscriptions after EOSE",
+        nips: [1],
+        stage: Preauth,
+        required: false,
+        run: eose::closes_complete_subscriptions_after_eose(),
+    },
+    KeepsOpenIncompleteSubscriptionsAfterEose {
+        name: "Keeps open incomplete subscriptions after EOSE",
+        nips: [1],
+        stage: Preauth,
+        required: true,
+        run: eose::keeps_open_incomplete_subscriptions_after_eose(),
+    },
 
     // Pre-Auth: public
-    PublicCanWrite,
-    AcceptsRelayListsFromPublic,
-    AcceptsDmRelayListsFromPublic,
-    AcceptsEphemeralEventsFromPublic,
+    PublicCanWrite {
+        name: "Public can write",
+        nips: [1],
+        stage: Preauth,
+        required: false,
+        run: public::public_can_write(),
+    },
+    AcceptsRelayListsFromPublic {
+        name: "Accepts relay lists from the public",
+        nips: [65],
+        stage: Preauth,
+        required: false,
+        run: public::accepts_relay_lists_from_public(),
+    },
+    AcceptsDmRelayListsFromPublic {
+        name: "Accepts DM relay lists from the public",
+        nips: [17],
+        stage: Preauth,
+        required: false,
+        run: public::accepts_dm_relay_lists_from_public(),
+    },
+    AcceptsEphemeralEventsFromPublic {
+        name: "Accepts ephemeral events from the public",
+        nips: [1],
+        stage: Preauth,
+        required: false,
+        run: public::accepts_ephemeral_events_from_public(),
+    },
 
     // Registered: reg
-    SendsOkAfterEvent,
-    VerifiesSignatures,
-    VerifiesIdHashes,
+    SendsOkAfterEvent {
+        name: "Sends OK after EVENT",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: reg::sends_ok_after_event(),
+    },
+    VerifiesSignatures {
+        name: "Verifies event signatures",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: reg::verifies_signatures(),
+    },
+    VerifiesIdHashes {
+        name: "Verifies event ID hashes",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: reg::verifies_id_hashes(),
+    },
+
+    // Registered: validation
+    RejectsUppercaseHexIds {
+        name: "Rejects uppercase hex ids",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: validation::uppercase_hex_id(),
+    },
+    RejectsWrongLengthPubkeys {
+        name: "Rejects wrong-length pubkeys",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::wrong_length_pubkey(),
+    },
+    RejectsWrongLengthSigs {
+        name: "Rejects wrong-length signatures",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::wrong_length_sig(),
+    },
+    RejectsMissingFields {
+        name: "Rejects events with missing fields",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::missing_fields(),
+    },
+    RejectsDuplicateFields {
+        name: "Rejects events with duplicate fields",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: validation::duplicate_fields(),
+    },
+    RejectsTagsNotArraysOfStrings {
+        name: "Rejects tags that are not arrays of strings",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::tags_not_arrays_of_strings(),
+    },
+    RejectsNumericTagElements {
+        name: "Rejects numeric tag elements",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::numeric_tag_elements(),
+    },
+    RejectsKindAsString {
+        name: "Rejects kind as a string",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::kind_as_string(),
+    },
+    RejectsKindAsFloat {
+        name: "Rejects kind as a float",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::kind_as_float(),
+    },
+    RejectsNegativeKinds {
+        name: "Rejects negative kinds",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: validation::negative_kind(),
+    },
 
     // Registered: json
-    AcceptsNip1JsonEscapeSequences,
-    AcceptsUnlistedJsonEscapeSequences,
-    AcceptsLiteralsForJsonEscapeSequences,
-    AcceptsUtf8NonCharacters,
+    AcceptsNip1JsonEscapeSequences {
+        name: "Accepts NIP-01 JSON escape sequences",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: json::nip1(),
+    },
+    AcceptsUnlistedJsonEscapeSequences {
+        name: "Accepts unlisted JSON escape sequences",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: json::unlisted(),
+    },
+    AcceptsLiteralsForJsonEscapeSequences {
+        name: "Accepts literals for JSON escape sequences",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: json::literals(),
+    },
+    AcceptsUtf8NonCharacters {
+        name: "Accepts UTF-8 non-characters",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: json::utf8non(),
+    },
+    PreservesJsonFieldOrder {
+        name: "Preserves JSON field order",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: json::field_order(),
+    },
+    PreservesNonstandardJsonFields {
+        name: "Preserves Non-standard JSON fields",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: json::nonstandard_fields(),
+    },
+    AcceptsNullCharacters {
+        name: "Accepts null character",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: json::null_characters(),
+    },
 
     // Registered: time
-    AcceptsEventsOneWeekOld,
-    AcceptsEventsOneMonthOld,
-    AcceptsEventsOneYearOld,
-    AcceptsEventsFromBeforeNostr,
-    AcceptsEventsFromBefore2000,
-    AcceptsEventsFrom1970,
-    AcceptsEventsFromBefore1970,
-    AcceptsEventsOneYearIntoTheFuture,
-    AcceptsEventsInTheDistantFuture,
-    AcceptsEventsWithCreatedAtGreaterThanSigned32Bit,
-    AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit,
-    AcceptsEventsWithCreatedAtInScientificNotation,
+    AcceptsEventsOneWeekOld {
+        name: "Accepts event.created_at one week old",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: time::one_week_ago(),
+    },
+    AcceptsEventsOneMonthOld {
+        name: "Accepts event.created_at one month old",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::one_month_ago(),
+    },
+    AcceptsEventsOneYearOld {
+        name: "Accepts event.created_at one year old",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::one_year_ago(),
+    },
+    AcceptsEventsFromBeforeNostr {
+        name: "Accepts event.created_at from before nostr",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::before_nostr(),
+    },
+    AcceptsEventsFromBefore2000 {
+        name: "Accepts event.created_at from before 2000",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::before_2000(),
+    },
+    AcceptsEventsFrom1970 {
+        name: "Accepts event.created_at from 1970",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::from_1970(),
+    },
+    AcceptsEventsFromBefore1970 {
+        name: "Accepts event.created_at from before 1970",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::before_1970(),
+    },
+    AcceptsEventsOneYearIntoTheFuture {
+        name: "Accepts event.created_at one year into the future",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::one_year_hence(),
+    },
+    AcceptsEventsInTheDistantFuture {
+        name: "Accepts event.created_at in the distant future",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::distant_future(),
+    },
+    AcceptsEventsWithCreatedAtGreaterThanSigned32Bit {
+        name: "Accepts event.created_at greater than signed 32-bit",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::greater_than_signed_32bit(),
+    },
+    AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit {
+        name: "Accepts event.created_at greater than unsigned 32-bit",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::greater_than_unsigned_32bit(),
+    },
+    AcceptsEventsWithCreatedAtInScientificNotation {
+        name: "Accepts event.created_at in scientific notation",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: time::scientific_notation(),
+    },
 
     // Registered: misc events
-    AcceptsEventsWithEmptyTags,
+    AcceptsEventsWithEmptyTags {
+        name: "Accepts events with empty tags",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: misc_events::empty_tags(),
+    },
+    HandlesEventKindLargerThan16bit {
+        name: "Handles event.kind > 16 bit",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: misc_events::kind_larger_than_16bit(),
+    },
 
     // Registered: find
-    EventsOrderedFromNewestToOldest,
-    NewestEventsWhenLimited,
-    FindById,
-    FindByPubkeyAndKind,
-    FindByPubkeyAndTags,
-    FindByKindAndTags,
-    FindByTags,
-    FindByMultipleTags,
-    FindByPubkey,
-    FindByScrape,
+    EventsOrderedFromNewestToOldest {
+        name: "Events are ordered from newest to oldest",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::newest_to_oldest(),
+    },
+    NewestEventsWhenLimited {
+        name: "Newest events are returned when filter is limited",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::newest_events_when_limited(),
+    },
+    FindById {
+        name: "Finds by id",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_id(),
+    },
+    FindByPubkeyAndKind {
+        name: "Finds by pubkey and kind",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_pubkey_and_kind(),
+    },
+    FindByPubkeyAndTags {
+        name: "Finds by pubkey and tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_pubkey_and_tags(),
+    },
+    FindByKindAndTags {
+        name: "Finds by kind and tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_kind_and_tags(),
+    },
+    FindByTags {
+        name: "Finds by tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_tags(),
+    },
+    FindByMultipleTags {
+        name: "Finds by multiple tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_multiple_tags(),
+    },
+    FindByPubkey {
+        name: "Finds by pubkey",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_pubkey(),
+    },
+    FindByScrape {
+        name: "Finds by scrape",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: find::find_by_scrape(),
+    },
 
     // Registered: filters
-    SinceUntilAreInclusive,
-    LimitZero,
+    SinceUntilAreInclusive {
+        name: "Since and until filters are inclusive",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: filters::since_until_are_inclusive(),
+    },
+    LimitZero {
+        name: "Limit zero works",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: filters::limit_zero(),
+    },
+    HandlesFilterKindLargerThan16bit {
+        name: "Handles filter.kinds > 16 bit",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::kind_larger_than_16bit(),
+    },
+    AcceptsNegativeFilterCreatedAt {
+        name: "Handles or explicitly rejects negative filter.since/until",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::negative_created_at(),
+    },
+    HandlesFilterPrefixes {
+        name: "Handles filter prefixes",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::prefixes(),
+    },
+    HandlesUnknownFilterFields {
+        name: "Handles unknown filter fields",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::unknown_fields(),
+    },
+    HandlesMultiLetterTagFilters {
+        name: "Handles multi-letter tag filters",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::multi_letter_tags(),
+    },
+    EmptyArrayFiltersMatchNothing {
+        name: "Empty array filters match nothing",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: filters::empty_arrays_match_nothing(),
+    },
 
     // Registered: ephemeral
-    EphemeralSubscriptionsWork,
-    PersistsEphemeralEvents,
+    EphemeralSubscriptionsWork {
+        name: "Ephemeral subscriptions work",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: ephemeral::ephemeral_subscriptions_work(),
+    },
+    PersistsEphemeralEvents {
+        name: "Persists ephemeral events",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: ephemeral::persists_ephemeral_events(),
+    },
 
     // Registered: replaceables
-    AcceptsMetadata,
-    ReplacesMetadata,
-    AcceptsContactlist,
-    ReplacesContactlist,
-    ReplacedEventsStillAvailableById,
-    ReplaceableEventRemovesPrevious,
-    ReplaceableEventRejectedIfFuture,
-    AddressableEventRemovesPrevious,
-    AddressableEventRejectedIfFuture,
-    FindReplaceableEvent,
-    FindAddressableEvent,
+    AcceptsMetadata {
+        name: "Accepts metadata",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::accepts_metadata(),
+    },
+    ReplacesMetadata {
+        name: "Replaces metadata",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        prerequisites: [AcceptsMetadata],
+        run: replaceables::replaces_metadata(),
+    },
+    AcceptsContactlist {
+        name: "Accepts Contactlists",
+        nips: [1, 2],
+        stage: Registered,
+        required: true,
+        run: replaceables::accepts_contact_list(),
+    },
+    ReplacesContactlist {
+        name: "Replaces Contactlists",
+        nips: [1, 2],
+        stage: Registered,
+        required: true,
+        prerequisites: [AcceptsContactlist],
+        run: replaceables::replaces_contact_list(),
+    },
+    ReplacedEventsStillAvailableById {
+        name: "Replaced events are still available by ID",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: replaceables::replaced_events_still_available_by_id(),
+    },
+    ReplaceableEventRemovesPrevious {
+        name: "Replaceable events replace older ones",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::replaceable_event_removes_previous(),
+    },
+    ReplaceableEventRejectedIfFuture {
+        name: "Replaceable events rejected if a newer one exists",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::replaceable_event_rejected_if_future(),
+    },
+    AddressableEventRemovesPrevious {
+        name: "Addressable events replace older ones",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::addressable_event_removes_previous(),
+    },
+    AddressableEventRejectedIfFuture {
+        name: "Addressable events rejected if a newer one exists",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::addressable_event_rejected_if_future(),
+    },
+    FindReplaceableEvent {
+        name: "Finds replaceable events",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::find_replaceable_event(),
+    },
+    FindAddressableEvent {
+        name: "Finds addressable events",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: replaceables::find_addressable_event(),
+    },
 
     // Registered: delete
-    DeleteById,
-    DeleteByAddr,
-    DeleteByAddrOnlyDeletesOlder,
-    DeleteByAddrIsBoundByTag,
-    DeleteByIdOfOthers,
-    DeleteByAddrOfOthers,
-    ResubmissionOfDeletedById,
-    ResubmissionOfOlderDeletedByAddr,
-    ResubmissionOfNewerDeletedByAddr,
+    DeleteById {
+        name: "Deletes by id",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_by_id(),
+    },
+    DeleteByAddr {
+        name: "Deletes by a-tag address",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_by_addr(),
+    },
+    DeleteByAddrOnlyDeletesOlder {
+        name: "Delete by a-tag deletes older but not newer",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteByAddr],
+        run: delete::delete_by_addr_only_older(),
+    },
+    DeleteByAddrIsBoundByTag {
+        name: "Delete by a-tag is bound by a-tag",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteByAddr],
+        run: delete::delete_by_addr_bound_by_tag(),
+    },
+    DeleteByIdOfOthers {
+        name: "Cannot delete by id of other people's events",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_by_id_of_others(),
+    },
+    DeleteByAddrOfOthers {
+        name: "Cannot delete by a-tag of other people's events",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_by_addr_of_others(),
+    },
+    ResubmissionOfDeletedById {
+        name: "Resubmission of deleted-by-id event is rejected",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteById],
+        run: delete::resubmission_of_delete_by_id(),
+    },
+    ResubmissionOfOlderDeletedByAddr {
+        name: "Resubmission of older deleted-by-addr event is rejected",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteByAddr],
+        run: delete::resubmission_of_older_delete_by_addr(),
+    },
+    ResubmissionOfNewerDeletedByAddr {
+        name: "Resubmission of newer deleted-by-addr event is accepted",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteByAddr],
+        run: delete::resubmission_of_newer_delete_by_addr(),
+    },
+    DeletionOfDeletionHasNoEffect {
+        name: "Deleting a deletion request does not undelete",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        prerequisites: [DeleteById],
+        run: delete::deletion_of_deletion(),
+    },
+    DeleteWithKTags {
+        name: "Deletes by id with a k-tag",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_with_k_tags(),
+    },
+    DeleteByIdAndAddrTogether {
+        name: "Deletes by e-tag and a-tag in one request",
+        nips: [9],
+        stage: Registered,
+        required: true,
+        run: delete::delete_by_id_and_addr_together(),
+    },
+    ServesDeletionRequests {
+        name: "Keeps serving deletion requests",
+        nips: [9],
+        stage: Registered,
+        required: false,
+        run: delete::serves_deletion_requests(),
+    },
+
+    // Registered: large
+    LargeContactLists {
+        name: "Supports large contact lists",
+        nips: [2],
+        stage: Registered,
+        required: true,
+        run: large::contact_list(1000, true),
+    },
+    HandlesContactListsWith5kEntries {
+        name: "Handles contact lists with 5k entries",
+        nips: [2],
+        stage: Registered,
+        required: false,
+        tags: ["slow"],
+        run: large::contact_list(5000, false),
+    },
+    HandlesContactListsWith10kEntries {
+        name: "Handles contact lists with 10k entries",
+        nips: [2],
+        stage: Registered,
+        required: false,
+        tags: ["slow"],
+        run: large::contact_list(10000, false),
+    },
+    HandlesContent64KB {
+        name: "Handles 64KB of content",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: large::content(64 * 1024),
+    },
+    HandlesContent256KB {
+        name: "Handles 256KB of content",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        tags: ["slow"],
+        run: large::content(256 * 1024),
+    },
+    HandlesContent1MB {
+        name: "Handles 1MB of content",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        tags: ["slow"],
+        run: large::content(1024 * 1024),
+    },
+    HandlesManyTagsWithManyValues {
+        name: "Handles 500 tags of 20 values each",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: large::many_tags(500, 20),
+    },
+
+    // Registered: unicode
+    HandlesSurrogatePairEscapes {
+        name: "Decodes escaped surrogate pairs",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: unicode::surrogate_pair_escapes(),
+    },
+    HandlesEmojiZwjSequences {
+        name: "Preserves emoji ZWJ sequences in content and tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: unicode::emoji_zwj_sequences(),
+    },
+    HandlesRtlMarks {
+        name: "Preserves RTL marks in content and tags",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: unicode::rtl_marks(),
+    },
+    RejectsOverlongUtf8 {
+        name: "Rejects overlong UTF-8 encodings",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: unicode::rejects_overlong_utf8(),
+    },
+    HandlesLoneSurrogateEscapes {
+        name: "Rejects or preserves lone surrogate escapes",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        run: unicode::lone_surrogate_escapes(),
+    },
+
+    // Registered: protected
+    ProtectedEventsRequireAuth {
+        name: "Rejects protected events without auth",
+        nips: [70, 42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: protected::rejected_without_auth(),
+    },
+    AcceptsProtectedEventsFromAuthedAuthor {
+        name: "Accepts protected events from the authenticated author",
+        nips: [70, 42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: protected::accepted_from_authed_author(),
+    },
+    RejectsProtectedEventsFromOtherAuthedKey {
+        name: "Rejects protected events from another authenticated key",
+        nips: [70, 42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        prerequisites: [AcceptsProtectedEventsFromAuthedAuthor],
+        run: protected::rejected_from_other_authed_key(),
+    },
+
+    // Registered: auth
+    SendsSingleAuthChallenge {
+        name: "Sends one AUTH challenge per connection",
+        nips: [42],
+        stage: Registered,
+        required: false,
+        run: auth::sends_single_challenge(),
+    },
+    AuthRejectsWrongChallenge {
+        name: "AUTH with the wrong challenge is rejected",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: auth::rejects_wrong_challenge(),
+    },
+    AuthRejectsWrongRelayTag {
+        name: "AUTH with the wrong relay tag is rejected",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: auth::rejects_wrong_relay_tag(),
+    },
+    AuthRejectsStaleTimestamp {
+        name: "AUTH 20 minutes old is rejected",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: auth::rejects_stale_timestamp(),
+    },
+    AuthRejectsWrongKind {
+        name: "AUTH with the wrong kind is rejected",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: auth::rejects_wrong_kind(),
+    },
+    AuthRejectsBadSignature {
+        name: "AUTH with a bad signature is rejected",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        tags: ["fresh-connection"],
+        run: auth::rejects_bad_signature(),
+    },
+    AuthAcceptsSecondKey {
+        name: "Accepts AUTH from a second key on the same connection",
+        nips: [42],
+        stage: Registered,
+        required: false,
+        tags: ["fresh-connection"],
+        run: auth::reauth_with_second_key(),
+    },
+    AuthRejectsUnsolicited {
+        name: "AUTH before any challenge is rejected",
+        nips: [42],
+        stage: Registered,
+        required: false,
+        tags: ["fresh-connection"],
+        run: auth::rejects_unsolicited_auth(),
+    },
+    AuthChallengesAreUnpredictable {
+        name: "AUTH challenges are unique and unpredictable",
+        nips: [42],
+        stage: Registered,
+        required: false,
+        tags: ["fresh-connection"],
+        run: auth::challenge_quality(),
+    },
+
+    // Registered: rate limits
+    ThrottlesPublishingGracefully {
+        name: "Throttles rapid publishing gracefully and recovers",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        tags: ["slow", "flood"],
+        run: rate_limit::publishing(),
+    },
+    ThrottlesSubscriptionsGracefully {
+        name: "Throttles rapid subscriptions gracefully and recovers",
+        nips: [1],
+        stage: Registered,
+        required: false,
+        tags: ["slow", "flood"],
+        run: rate_limit::subscriptions(),
+    },
+
+    // Registered: vanish
+    VanishRemovesAllEvents {
+        name: "Request to vanish removes all of the key's events",
+        nips: [62],
+        stage: Registered,
+        required: false,
+        tags: ["destructive"],
+        run: vanish::removes_all_events(),
+    },
+    VanishRefusesOlderRepublication {
+        name: "Refuses republication of events from before a vanish",
+        nips: [62],
+        stage: Registered,
+        required: false,
+        tags: ["destructive"],
+        prerequisites: [VanishRemovesAllEvents],
+        run: vanish::refuses_older_republication(),
+    },
+    VanishLeavesOthersAlone {
+        name: "Request to vanish leaves other authors alone",
+        nips: [62],
+        stage: Registered,
+        required: false,
+        tags: ["destructive"],
+        run: vanish::leaves_others_alone(),
+    },
+    VanishForAllRelays {
+        name: "Honors requests to vanish from ALL_RELAYS",
+        nips: [62],
+        stage: Registered,
+        required: false,
+        run: vanish::all_relays(),
+    },
 
     // TBD
-    LimitWorksAcrossMultipleFilterGroups,
-    ServesPostEoseEvents,
-    NoTimeoutWhileSubscribed,
-    Nip4DmsRequireAuth,
-    CanAuthAsUnknown,
-    UnknownCanWriteOwn,
-    UnknownCanReadbackOwn,
-    UnknownCanWriteOther,
-    UnknownCanReadbackOther,
-    CanAuthAsKnown,
-    KnownCanWriteOwn,
-    KnownCanReadbackOwn,
-    KnownCanWriteOther,
-    KnownCanReadbackOther,
-    GiftwrapsRequireAuth,
-    LargeContactLists,
-    PreservesJsonFieldOrder,
-    PreservesNonstandardJsonFields,
-    HandlesEventKindLargerThan16bit,
-    HandlesFilterKindLargerThan16bit,
-    AcceptsNegativeFilterCreatedAt,
-    AcceptsNullCharacters,
-    HandlesFilterPrefixes,
-    MaxSubscriptions,
-    MaxConnections,
-    AllowsImmediateReconnect,
-    IdleTimeoutIfUnsubscribed,
+    LimitWorksAcrossMultipleFilterGroups {
+        name: "Limit works across multiple filter groups",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    ServesPostEoseEvents {
+        name: "Serves post-EOSE events",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    NoTimeoutWhileSubscribed {
+        name: "No timeout while subscribed",
+        nips: [1],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    Nip4DmsRequireAuth {
+        name: "Nip-04 DMs require AUTH",
+        nips: [4, 42],
+        stage: Stranger,
+        required: false,
+        run: tbd(),
+    },
+    CanAuthAsUnknown {
+        name: "Can AUTH as unknown",
+        nips: [42],
+        stage: Stranger,
+        required: false,
+        run: tbd(),
+    },
+    UnknownCanWriteOwn {
+        name: "Unknown can write own",
+        nips: [42],
+        stage: Stranger,
+        required: true,
+        run: tbd(),
+    },
+    UnknownCanReadbackOwn {
+        name: "Unknown can read back own",
+        nips: [42],
+        stage: Stranger,
+        required: true,
+        run: tbd(),
+    },
+    UnknownCanWriteOther {
+        name: "Unknown can write other",
+        nips: [42],
+        stage: Stranger,
+        required: true,
+        run: tbd(),
+    },
+    UnknownCanReadbackOther {
+        name: "Unknown can read back other",
+        nips: [42],
+        stage: Stranger,
+        required: true,
+        run: tbd(),
+    },
+    CanAuthAsKnown {
+        name: "Can AUTH as known",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    KnownCanWriteOwn {
+        name: "Known can write own",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    KnownCanReadbackOwn {
+        name: "Known can read back own",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    KnownCanWriteOther {
+        name: "Known can write other",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    KnownCanReadbackOther {
+        name: "Known can readback other",
+        nips: [42],
+        stage: Registered,
+        required: true,
+        run: tbd(),
+    },
+    GiftwrapsRequireAuth {
+        name: "Giftwraps require AUTH",
+        nips: [59, 42],
+        stage: Stranger,
+        required: true,
+        run: tbd(),
+    },
+    MaxSubscriptions {
+        name: "Max subscriptions",
+        nips: [11],
+        stage: Registered,
+        required: false,
+        run: tbd(),
+    },
+    MaxConnections {
+        name: "Max connections",
+        nips: [],
+        stage: Registered,
+        required: false,
+        run: tbd(),
+    },
+    AllowsImmediateReconnect {
+        name: "Allows immediate reconnect",
+        nips: [],
+        stage: Registered,
+        required: false,
+        run: tbd(),
+    },
+    IdleTimeoutIfUnsubscribed {
+        name: "Idle timeout if unsubscribed",
+        nips: [],
+        stage: Registered,
+        required: false,
+        run: tbd(),
+    },
 }
 
 impl TestItem {
     pub fn name(&self) -> &'static str {
-        use TestItem::*;
-
-        match *self {
-            // Pre-Auth: nip11
-            Nip11Provided => "NIP-11 document is provided",
-            ClaimsSupportForNip4 => "Claims support for NIP-04 (old DMs)",
-            ClaimsSupportForNip9 => "Claims support for NIP-09 (Deletion)",
-            ClaimsSupportForNip11 => "Claims support for NIP-11 (Relay Information Document)",
-            ClaimsSupportForNip26 => "Claims support for NIP-26 (Delegated Event Signing)",
-            ClaimsSupportForNip29 => "Claims support for NIP-29 (Relay-based Groups)",
-            ClaimsSupportForNip40 => "Claims support for NIP-40 (Expiration Timestamp)",
-            ClaimsSupportForNip42 => "Claims support for NIP-42 (AUTH)",
-            ClaimsSupportForNip45 => "Claims support for NIP-45 (COUNT)",
-            ClaimsSupportForNip50 => "Claims support for NIP-50 (SEARCH)",
-            ClaimsSupportForNip59 => "Claims support for NIP-59 (Giftwrap)",
-            ClaimsSupportForNip65 => "Claims support for NIP-65 (Relay Lists)",
-            ClaimsSupportForNip94 => "Claims support for NIP-94 (File Metadata)",
-            ClaimsSupportForNip96 => "Claims support for NIP-96 (HTTP file storage)",
-
-            // Pre-Auth: auth
-            PromptsForAuthInitially => "Prompts for AUTH when client connects",
-
-            // Pre-Auth: eose
-            SupportsEose => "Supports EOSE",
-            ClosesCompleteSubscriptionsAfterEose => "Closes complete subscriptions after EOSE",
-            KeepsOpenIncompleteSubscriptionsAfterEose => {
-                "Keeps open incomplete subscriptions after EOSE"
-            }
-
-            // Pre-Auth: public
-            PublicCanWrite => "Public can write",
-            AcceptsRelayListsFromPublic => "Accepts relay lists from the public",
-            AcceptsDmRelayListsFromPublic => "Accepts DM relay lists from the public",
-            AcceptsEphemeralEventsFromPublic => "Accepts ephemeral events from the public",
-
-            // Registered: reg
-            SendsOkAfterEvent => "Sends OK after EVENT",
-            VerifiesSignatures => "Verifies event signatures",
-            VerifiesIdHashes => "Verifies event ID hashes",
-
-            // Registered: json
-            AcceptsNip1JsonEscapeSequences => "Accepts NIP-01 JSON escape sequences",
-            AcceptsUnlistedJsonEscapeSequences => "Accepts unlisted JSON escape sequences",
-            AcceptsLiteralsForJsonEscapeSequences => "Accepts literals for JSON escape sequences",
-            AcceptsUtf8NonCharacters => "Accepts UTF-8 non-characters",
-
-            // Registered: time
-            AcceptsEventsOneWeekOld => "Accepts event.created_at one week old",
-            AcceptsEventsOneMonthOld => "Accepts event.created_at one month old",
-            AcceptsEventsOneYearOld => "Accepts event.created_at one year old",
-            AcceptsEventsFromBeforeNostr => "Accepts event.created_at from before nostr",
-            AcceptsEventsFromBefore2000 => "Accepts event.created_at from before 2000",
-            AcceptsEventsFrom1970 => "Accepts event.created_at from 1970",
-            AcceptsEventsFromBefore1970 => "Accepts event.created_at from before 1970",
-            AcceptsEventsOneYearIntoTheFuture => {
-                "Accepts event.created_at one year into the future"
-            }
-            AcceptsEventsInTheDistantFuture => "Accepts event.created_at in the distant future",
-            AcceptsEventsWithCreatedAtGreaterThanSigned32Bit => {
-                "Accepts event.created_at greater than signed 32-bit"
-            }
-            AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit => {
-                "Accepts event.created_at greater than unsigned 32-bit"
-            }
-            AcceptsEventsWithCreatedAtInScientificNotation => {
-                "Accepts event.created_at in scientific notation"
-            }
-
-            // Registered: misc events
-            AcceptsEventsWithEmptyTags => "Accepts events with empty tags",
-
-            // Registered: find
-            EventsOrderedFromNewestToOldest => "Events are ordered from newest to oldest",
-            NewestEventsWhenLimited => "Newest events are returned when filter is limited",
-            FindById => "Finds by id",
-            FindByPubkeyAndKind => "Finds by pubkey and kind",
-            FindByPubkeyAndTags => "Finds by pubkey and tags",
-            FindByKindAndTags => "Finds by kind and tags",
-            FindByTags => "Finds by tags",
-            FindByMultipleTags => "Finds by multiple tags",
-            FindByPubkey => "Finds by pubkey",
-            FindByScrape => "Finds by scrape",
-
-            // Registered: filters
-            SinceUntilAreInclusive => "Since and until filters are inclusive",
-            LimitZero => "Limit zero works",
-
-            // Registered: ephemeral
-            EphemeralSubscriptionsWork => "Ephemeral subscriptions work",
-            PersistsEphemeralEvents => "Persists ephemeral events",
-
-            // Registered: replaceables
-            AcceptsMetadata => "Accepts metadata",
-            ReplacesMetadata => "Replaces metadata",
-            AcceptsContactlist => "Accepts Contactlists",
-            ReplacesContactlist => "Replaces Contactlists",
-            ReplacedEventsStillAvailableById => "Replaced events are still available by ID",
-            ReplaceableEventRemovesPrevious => "Replaceable events replace older ones",
-            ReplaceableEventRejectedIfFuture => "Replaceable events rejected if a newer one exists",
-            AddressableEventRemovesPrevious => "Addressable events replace older ones",
-            AddressableEventRejectedIfFuture => "Addressable events rejected if a newer one exists",
-            FindReplaceableEvent => "Finds replaceable events",
-            FindAddressableEvent => "Finds addressable events",
-
-            // Registered: delete
-            DeleteById => "Deletes by id",
-            DeleteByAddr => "Deletes by a-tag address",
-            DeleteByAddrOnlyDeletesOlder => "Delete by a-tag deletes older but not newer",
-            DeleteByAddrIsBoundByTag => "Delete by a-tag is bound by a-tag",
-            DeleteByIdOfOthers => "Cannot delete by id of other people's events",
-            DeleteByAddrOfOthers => "Cannot delete by a-tag of other people's events",
-            ResubmissionOfDeletedById => "Resubmission of deleted-by-id event is rejected",
-            ResubmissionOfOlderDeletedByAddr => {
-                "Resubmission of older deleted-by-addr event is rejected"
-            }
-            ResubmissionOfNewerDeletedByAddr => {
-                "Resubmission of newer deleted-by-addr event is accepted"
-            }
-
-            // TBD
-            LimitWorksAcrossMultipleFilterGroups => "Limit works across multiple filter groups",
-            ServesPostEoseEvents => "Serves post-EOSE events",
-            NoTimeoutWhileSubscribed => "No timeout while subscribed",
-            Nip4DmsRequireAuth => "Nip-04 DMs require AUTH",
-            CanAuthAsUnknown => "Can AUTH as unknown",
-            UnknownCanWriteOwn => "Unknown can write own",
-            UnknownCanReadbackOwn => "Unknown can read back own",
-            UnknownCanWriteOther => "Unknown can write other",
-            UnknownCanReadbackOther => "Unknown can read back other",
-            CanAuthAsKnown => "Can AUTH as known",
-            KnownCanWriteOwn => "Known can write own",
-            KnownCanReadbackOwn => "Known can read back own",
-            KnownCanWriteOther => "Known can write other",
-            KnownCanReadbackOther => "Known can readback other",
-            GiftwrapsRequireAuth => "Giftwraps require AUTH",
-            LargeContactLists => "Supports large contact lists",
-            PreservesJsonFieldOrder => "Preserves JSON field order",
-            PreservesNonstandardJsonFields => "Preserves Non-standard JSON fields",
-            HandlesEventKindLargerThan16bit => "Handles event.kind > 16 bit",
-            HandlesFilterKindLargerThan16bit => "Handles filter.kinds > 16 bit",
-            AcceptsNegativeFilterCreatedAt => "Accepts negative filter.since/until",
-            AcceptsNullCharacters => "Accepts null character",
-            HandlesFilterPrefixes => "Handles filter prefixes",
-            MaxSubscriptions => "Max subscriptions",
-            MaxConnections => "Max connections",
-            AllowsImmediateReconnect => "Allows immediate reconnect",
-            IdleTimeoutIfUnsubscribed => "Idle timeout if unsubscribed",
-        }
+        self.info().name
     }
 
     pub fn required(&self) -> bool {
-        use TestItem::*;
-
-        match *self {
-            // Pre-Auth: nip11
-            Nip11Provided => false,
-            ClaimsSupportForNip4 => false,
-            ClaimsSupportForNip9 => false,
-            ClaimsSupportForNip11 => false,
-            ClaimsSupportForNip26 => false,
-            ClaimsSupportForNip29 => false,
-            ClaimsSupportForNip40 => false,
-            ClaimsSupportForNip42 => false,
-            ClaimsSupportForNip45 => false,
-            ClaimsSupportForNip50 => false,
-            ClaimsSupportForNip59 => false,
-            ClaimsSupportForNip65 => false,
-            ClaimsSupportForNip94 => false,
-            ClaimsSupportForNip96 => false,
-
-            // Pre-Auth: auth
-            PromptsForAuthInitially => false,
-
-            // Pre-Auth: eose
-            SupportsEose => true,
-            ClosesCompleteSubscriptionsAfterEose => false,
-            KeepsOpenIncompleteSubscriptionsAfterEose => true,
-
-            // Pre-Auth: public
-            PublicCanWrite => false,
-            AcceptsRelayListsFromPublic => false,
-            AcceptsDmRelayListsFromPublic => false,
-            AcceptsEphemeralEventsFromPublic => false,
-
-            // Registered: reg
-            SendsOkAfterEvent => true,
-            VerifiesSignatures => true,
-            VerifiesIdHashes => true,
-
-            // Registered: json
-            AcceptsNip1JsonEscapeSequences => true,
-            AcceptsUnlistedJsonEscapeSequences => false,
-            AcceptsLiteralsForJsonEscapeSequences => false,
-            AcceptsUtf8NonCharacters => true,
-
-            // Registered: time
-            AcceptsEventsOneWeekOld => true,
-            AcceptsEventsOneMonthOld => false,
-            AcceptsEventsOneYearOld => false,
-            AcceptsEventsFromBeforeNostr => false,
-            AcceptsEventsFromBefore2000 => false,
-            AcceptsEventsFrom1970 => false,
-            AcceptsEventsFromBefore1970 => false,
-            AcceptsEventsOneYearIntoTheFuture => false,
-            AcceptsEventsInTheDistantFuture => false,
-            AcceptsEventsWithCreatedAtGreaterThanSigned32Bit => false,
-            AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit => false,
-            AcceptsEventsWithCreatedAtInScientificNotation => false,
-
-            // Registered: misc events
-            AcceptsEventsWithEmptyTags => false,
-
-            // Registered: find
-            EventsOrderedFromNewestToOldest => true,
-            NewestEventsWhenLimited => true,
-            FindById => true,
-            FindByPubkeyAndKind => true,
-            FindByPubkeyAndTags => true,
-            FindByKindAndTags => true,
-            FindByTags => true,
-            FindByMultipleTags => true,
-            FindByPubkey => true,
-            FindByScrape => true,
-
-            // Registered: filters
-            SinceUntilAreInclusive => true,
-            LimitZero => true,
-
-            // Registered: ephemeral
-            EphemeralSubscriptionsWork => false,
-            PersistsEphemeralEvents => false,
-
-            // Registered: replaceables
-            AcceptsMetadata => true,
-            ReplacesMetadata => true,
-            AcceptsContactlist => true,
-            ReplacesContactlist => true,
-            ReplacedEventsStillAvailableById => false,
-            ReplaceableEventRemovesPrevious => true,
-            ReplaceableEventRejectedIfFuture => true,
-            AddressableEventRemovesPrevious => true,
-            AddressableEventRejectedIfFuture => true,
-            FindReplaceableEvent => true,
-            FindAddressableEvent => true,
-
-            // Registered: delete
-            DeleteById => true,
-            DeleteByAddr => true,
-            DeleteByAddrOnlyDeletesOlder => true,
-            DeleteByAddrIsBoundByTag => true,
-            DeleteByIdOfOthers => true,
-            DeleteByAddrOfOthers => true,
-            ResubmissionOfDeletedById => true,
-            ResubmissionOfOlderDeletedByAddr => true,
-            ResubmissionOfNewerDeletedByAddr => true,
-
-            // TBD
-            LimitWorksAcrossMultipleFilterGroups => true,
-            ServesPostEoseEvents => true,
-            NoTimeoutWhileSubscribed => true,
-            Nip4DmsRequireAuth => false,
-            GiftwrapsRequireAuth => true,
-            CanAuthAsUnknown => false,
-            UnknownCanWriteOwn => true,
-            UnknownCanReadbackOwn => true,
-            UnknownCanWriteOther => true,
-            UnknownCanReadbackOther => true,
-            CanAuthAsKnown => true,
-            KnownCanWriteOwn => true,
-            KnownCanReadbackOwn => true,
-            KnownCanWriteOther => true,
-            KnownCanReadbackOther => true,
-            LargeContactLists => true,
-            PreservesJsonFieldOrder => false,
-            PreservesNonstandardJsonFields => false,
-            HandlesEventKindLargerThan16bit => false,
-            HandlesFilterKindLargerThan16bit => false,
-            AcceptsNegativeFilterCreatedAt => false,
-            AcceptsNullCharacters => false,
-            HandlesFilterPrefixes => false,
-            MaxSubscriptions => false,
-            MaxConnections => false,
-            AllowsImmediateReconnect => false,
-            IdleTimeoutIfUnsubscribed => false,
-        }
+        self.info().required
     }
 
     pub fn stage(&self) -> Stage {
-        use TestItem::*;
-
-        match *self {
-            // Pre-Auth: nip11
-            Nip11Provided => Stage::Preauth,
-            ClaimsSupportForNip4 => Stage::Preauth,
-            ClaimsSupportForNip9 => Stage::Preauth,
-            ClaimsSupportForNip11 => Stage::Preauth,
-            ClaimsSupportForNip26 => Stage::Preauth,
-            ClaimsSupportForNip29 => Stage::Preauth,
-            ClaimsSupportForNip40 => Stage::Preauth,
-            ClaimsSupportForNip42 => Stage::Preauth,
-            ClaimsSupportForNip45 => Stage::Preauth,
-            ClaimsSupportForNip50 => Stage::Preauth,
-            ClaimsSupportForNip59 => Stage::Preauth,
-            ClaimsSupportForNip65 => Stage::Preauth,
-            ClaimsSupportForNip94 => Stage::Preauth,
-            ClaimsSupportForNip96 => Stage::Preauth,
-
-            // Pre-Auth: auth
-            PromptsForAuthInitially => Stage::Preauth,
-
-            // Pre-Auth: eose
-            SupportsEose => Stage::Preauth,
-            ClosesCompleteSubscriptionsAfterEose => Stage::Preauth,
-            KeepsOpenIncompleteSubscriptionsAfterEose => Stage::Preauth,
-
-            // Pre-Auth: public
-            PublicCanWrite => Stage::Preauth,
-            AcceptsRelayListsFromPublic => Stage::Preauth,
-            AcceptsDmRelayListsFromPublic => Stage::Preauth,
-            AcceptsEphemeralEventsFromPublic => Stage::Preauth,
-
-            // Registered: reg
-            SendsOkAfterEvent => Stage::Registered,
-            VerifiesSignatures => Stage::Registered,
-            VerifiesIdHashes => Stage::Registered,
-
-            // Registered: json
-            AcceptsNip1JsonEscapeSequences => Stage::Registered,
-            AcceptsUnlistedJsonEscapeSequences => Stage::Registered,
-            AcceptsLiteralsForJsonEscapeSequences => Stage::Registered,
-            AcceptsUtf8NonCharacters => Stage::Registered,
-
-            // Registered: time
-            AcceptsEventsOneWeekOld => Stage::Registered,
-            AcceptsEventsOneMonthOld => Stage::Registered,
-            AcceptsEventsOneYearOld => Stage::Registered,
-            AcceptsEventsFromBeforeNostr => Stage::Registered,
-            AcceptsEventsFromBefore2000 => Stage::Registered,
-            AcceptsEventsFrom1970 => Stage::Registered,
-            AcceptsEventsFromBefore1970 => Stage::Registered,
-            AcceptsEventsOneYearIntoTheFuture => Stage::Registered,
-            AcceptsEventsInTheDistantFuture => Stage::Registered,
-            AcceptsEventsWithCreatedAtGreaterThanSigned32Bit => Stage::Registered,
-            AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit => Stage::Registered,
-            AcceptsEventsWithCreatedAtInScientificNotation => Stage::Registered,
-
-            // Registered: misc events
-            AcceptsEventsWithEmptyTags => Stage::Registered,
-
-            // Registered: find
-            EventsOrderedFromNewestToOldest => Stage::Registered,
-            NewestEventsWhenLimited => Stage::Registered,
-            FindById => Stage::Registered,
-            FindByPubkeyAndKind => Stage::Registered,
-            FindByPubkeyAndTags => Stage::Registered,
-            FindByKindAndTags => Stage::Registered,
-            FindByTags => Stage::Registered,
-            FindByMultipleTags => Stage::Registered,
-            FindByPubkey => Stage::Registered,
-            FindByScrape => Stage::Registered,
-
-            // Registered: filters
-            SinceUntilAreInclusive => Stage::Registered,
-            LimitZero => Stage::Registered,
-
-            // Registered: ephemeral
-            EphemeralSubscriptionsWork => Stage::Registered,
-            PersistsEphemeralEvents => Stage::Registered,
-
-            // Registered: replaceables
-            AcceptsMetadata => Stage::Registered,
-            ReplacesMetadata => Stage::Registered,
-            AcceptsContactlist => Stage::Registered,
-            ReplacesContactlist => Stage::Registered,
-            ReplacedEventsStillAvailableById => Stage::Registered,
-            ReplaceableEventRemovesPrevious => Stage::Registered,
-            ReplaceableEventRejectedIfFuture => Stage::Registered,
-            AddressableEventRemovesPrevious => Stage::Registered,
-            AddressableEventRejectedIfFuture => Stage::Registered,
-            FindReplaceableEvent => Stage::Registered,
-            FindAddressableEvent => Stage::Registered,
-
-            // Registered: delete
-            DeleteById => Stage::Registered,
-            DeleteByAddr => Stage::Registered,
-            DeleteByAddrOnlyDeletesOlder => Stage::Registered,
-            DeleteByAddrIsBoundByTag => Stage::Registered,
-            DeleteByIdOfOthers => Stage::Registered,
-            DeleteByAddrOfOthers => Stage::Registered,
-            ResubmissionOfDeletedById => Stage::Registered,
-            ResubmissionOfOlderDeletedByAddr => Stage::Registered,
-            ResubmissionOfNewerDeletedByAddr => Stage::Registered,
-
-            // TBD
-            LimitWorksAcrossMultipleFilterGroups => Stage::Registered,
-            ServesPostEoseEvents => Stage::Registered,
-            NoTimeoutWhileSubscribed => Stage::Registered,
-            CanAuthAsKnown => Stage::Registered,
-            KnownCanWriteOwn => Stage::Registered,
-            KnownCanReadbackOwn => Stage::Registered,
-            KnownCanWriteOther => Stage::Registered,
-            KnownCanReadbackOther => Stage::Registered,
-            LargeContactLists => Stage::Registered,
-            PreservesJsonFieldOrder => Stage::Registered,
-            PreservesNonstandardJsonFields => Stage::Registered,
-            HandlesEventKindLargerThan16bit => Stage::Registered,
-            HandlesFilterKindLargerThan16bit => Stage::Registered,
-            AcceptsNegativeFilterCreatedAt => Stage::Registered,
-            AcceptsNullCharacters => Stage::Registered,
-            HandlesFilterPrefixes => Stage::Registered,
-            MaxSubscriptions => Stage::Registered,
-            MaxConnections => Stage::Registered,
-            AllowsImmediateReconnect => Stage::Registered,
-            IdleTimeoutIfUnsubscribed => Stage::Registered,
-
-            // Stranger
-            // ...
-            Nip4DmsRequireAuth => Stage::Stranger,
-            GiftwrapsRequireAuth => Stage::Stranger,
-            CanAuthAsUnknown => Stage::Stranger,
-            UnknownCanWriteOwn => Stage::Stranger,
-            UnknownCanReadbackOwn => Stage::Stranger,
-            UnknownCanWriteOther => Stage::Stranger,
-            UnknownCanReadbackOther => Stage::Stranger,
-        }
+        self.info().stage
     }
 
-    pub async fn run(&self) -> Outcome {
-        use TestItem::*;
-
-        use crate::tests::{
-            auth, delete, eose, ephemeral, filters, find, json, misc_events, nip11, public, reg,
-            replaceables, tbd, time,
-        };
-
-        let result = match *self {
-            // Pre-Auth: nip11
-            Nip11Provided => nip11::nip11_provided().await,
-            ClaimsSupportForNip4 => nip11::claimed_support_for_nip(4).await,
-            ClaimsSupportForNip9 => nip11::claimed_support_for_nip(9).await,
-            ClaimsSupportForNip11 => nip11::claimed_support_for_nip(11).await,
-            ClaimsSupportForNip26 => nip11::claimed_support_for_nip(26).await,
-            ClaimsSupportForNip29 => nip11::claimed_support_for_nip(29).await,
-            ClaimsSupportForNip40 => nip11::claimed_support_for_nip(40).await,
-            ClaimsSupportForNip42 => nip11::claimed_support_for_nip(42).await,
-            ClaimsSupportForNip45 => nip11::claimed_support_for_nip(45).await,
-            ClaimsSupportForNip50 => nip11::claimed_support_for_nip(50).await,
-            ClaimsSupportForNip59 => nip11::claimed_support_for_nip(59).await,
-            ClaimsSupportForNip65 => nip11::claimed_support_for_nip(65).await,
-            ClaimsSupportForNip94 => nip11::claimed_support_for_nip(94).await,
-            ClaimsSupportForNip96 => nip11::claimed_support_for_nip(96).await,
-
-            // Pre-Auth: auth
-            PromptsForAuthInitially => auth::prompts_for_auth_initially().await,
-
-            // Pre-Auth: eose
-            SupportsEose => eose::supports_eose().await,
-            ClosesCompleteSubscriptionsAfterEose => {
-                eose::closes_complete_subscriptions_after_eose().await
-            }
-            KeepsOpenIncompleteSubscriptionsAfterEose => {
-                eose::keeps_open_incomplete_subscriptions_after_eose().await
-            }
+    pub fn nips(&self) -> &'static [u32] {
+        self.info().nips
+    }
 
-            // Pre-Auth: public
-            PublicCanWrite => public::public_can_write().await,
-            AcceptsRelayListsFromPublic => public::accepts_relay_lists_from_public().await,
-            AcceptsDmRelayListsFromPublic => public::accepts_dm_relay_lists_from_public().await,
-            AcceptsEphemeralEventsFromPublic => {
-                public::accepts_ephemeral_events_from_public().await
-            }
+    // Flooding the relay can get us blocked for longer than any test waits,
+    // failing every test after it, so these only run when asked for
+    pub fn floods(&self) -> bool {
+        self.info().tags.contains(&"flood")
+    }
 
-            // Registered: reg
-            SendsOkAfterEvent => reg::sends_ok_after_event().await,
-            VerifiesSignatures => reg::verifies_signatures().await,
-            VerifiesIdHashes => reg::verifies_id_hashes().await,
-
-            // Registered: json
-            AcceptsNip1JsonEscapeSequences => json::nip1().await,
-            AcceptsUnlistedJsonEscapeSequences => json::unlisted().await,
-            AcceptsLiteralsForJsonEscapeSequences => json::literals().await,
-            AcceptsUtf8NonCharacters => json::utf8non().await,
-
-            // Registered: time
-            AcceptsEventsOneWeekOld => time::one_week_ago().await,
-            AcceptsEventsOneMonthOld => time::one_month_ago().await,
-            AcceptsEventsOneYearOld => time::one_year_ago().await,
-            AcceptsEventsFromBeforeNostr => time::before_nostr().await,
-            AcceptsEventsFromBefore2000 => time::before_2000().await,
-            AcceptsEventsFrom1970 => time::from_1970().await,
-            AcceptsEventsFromBefore1970 => time::before_1970().await,
-            AcceptsEventsOneYearIntoTheFuture => time::one_year_hence().await,
-            AcceptsEventsInTheDistantFuture => time::distant_future().await,
-            AcceptsEventsWithCreatedAtGreaterThanSigned32Bit => {
-                time::greater_than_signed_32bit().await
-            }
-            AcceptsEventsWithCreatedAtGreaterThanUnsigned32Bit => {
-                time::greater_than_unsigned_32bit().await
-            }
-            AcceptsEventsWithCreatedAtInScientificNotation => time::scientific_notation().await,
-
-            // Registered: misc_events
-            AcceptsEventsWithEmptyTags => misc_events::empty_tags().await,
-
-            // Registered: find
-            EventsOrderedFromNewestToOldest => find::newest_to_oldest().await,
-            NewestEventsWhenLimited => find::newest_events_when_limited().await,
-            FindById => find::find_by_id().await,
-            FindByPubkeyAndKind => find::find_by_pubkey_and_kind().await,
-            FindByPubkeyAndTags => find::find_by_pubkey_and_tags().await,
-            FindByKindAndTags => find::find_by_kind_and_tags().await,
-            FindByTags => find::find_by_tags().await,
-            FindByMultipleTags => find::find_by_multiple_tags().await,
-            FindByPubkey => find::find_by_pubkey().await,
-            FindByScrape => find::find_by_scrape().await,
-
-            // Registered: filters
-            SinceUntilAreInclusive => filters::since_until_are_inclusive().await,
-            LimitZero => filters::limit_zero().await,
-
-            // Registered: ephemeral
-            EphemeralSubscriptionsWork => ephemeral::ephemeral_subscriptions_work().await,
-            PersistsEphemeralEvents => ephemeral::persists_ephemeral_events().await,
-
-            // Registered: replaceables
-            AcceptsMetadata => replaceables::accepts_metadata().await,
-            ReplacesMetadata => replaceables::replaces_metadata().await,
-            AcceptsContactlist => replaceables::accepts_contact_list().await,
-            ReplacesContactlist => replaceables::replaces_contact_list().await,
-            ReplacedEventsStillAvailableById => {
-                replaceables::replaced_events_still_available_by_id().await
-            }
-            ReplaceableEventRemovesPrevious => {
-                replaceables::replaceable_event_removes_previous().await
-            }
-            ReplaceableEventRejectedIfFuture => {
-                replaceables::replaceable_event_rejected_if_future().await
-            }
-            AddressableEventRemovesPrevious => {
-                replaceables::addressable_event_removes_previous().await
-            }
-            AddressableEventRejectedIfFuture => {
-                replaceables::addressable_event_rejected_if_future().await
-            }
-            FindReplaceableEvent => replaceables::find_replaceable_event().await,
-            FindAddressableEvent => replaceables::find_addressable_event().await,
-
-            // Registered: delete
-            DeleteById => delete::delete_by_id().await,
-            DeleteByAddr => delete::delete_by_addr().await,
-            DeleteByAddrOnlyDeletesOlder => delete::delete_by_addr_only_older().await,
-            DeleteByAddrIsBoundByTag => delete::delete_by_addr_bound_by_tag().await,
-            DeleteByIdOfOthers => delete::delete_by_id_of_others().await,
-            DeleteByAddrOfOthers => delete::delete_by_addr_of_others().await,
-            ResubmissionOfDeletedById => delete::resubmission_of_delete_by_id().await,
-            ResubmissionOfOlderDeletedByAddr => {
-                delete::resubmission_of_older_delete_by_addr().await
-            }
-            ResubmissionOfNewerDeletedByAddr => {
-                delete::resubmission_of_newer_delete_by_addr().await
-            }
+    // A request to vanish erases the registered key's events and, on a relay
+    // that implements NIP-62, makes it refuse them again (event group A is
+    // backdated), so a re-run soon after fails for unrelated reasons. These
+    // only run when asked for.
+    pub fn destructive(&self) -> bool {
+        self.info().tags.contains(&"destructive")
+    }
 
-            // TBD
-            LimitWorksAcrossMultipleFilterGroups => tbd(),
-            ServesPostEoseEvents => tbd(),
-            NoTimeoutWhileSubscribed => tbd(),
-            Nip4DmsRequireAuth => tbd(),
-            GiftwrapsRequireAuth => tbd(),
-            CanAuthAsUnknown => tbd(),
-            UnknownCanWriteOwn => tbd(),
-            UnknownCanReadbackOwn => tbd(),
-            UnknownCanWriteOther => tbd(),
-            UnknownCanReadbackOther => tbd(),
-            CanAuthAsKnown => tbd(),
-            KnownCanWriteOwn => tbd(),
-            KnownCanReadbackOwn => tbd(),
-            KnownCanWriteOther => tbd(),
-            KnownCanReadbackOther => tbd(),
-            LargeContactLists => tbd(),
-            PreservesJsonFieldOrder => tbd(),
-            PreservesNonstandardJsonFields => tbd(),
-            HandlesEventKindLargerThan16bit => tbd(),
-            HandlesFilterKindLargerThan16bit => tbd(),
-            AcceptsNegativeFilterCreatedAt => tbd(),
-            AcceptsNullCharacters => tbd(),
-            HandlesFilterPrefixes => tbd(),
-            MaxSubscriptions => tbd(),
-            MaxConnections => tbd(),
-            AllowsImmediateReconnect => tbd(),
-            IdleTimeoutIfUnsubscribed => tbd(),
-        };
-
-        match result {
+    pub async fn run(&self) -> Outcome {
+        match self.run_inner().await {
             Ok(outcome) => outcome,
             Err(e) => match e {
                 Error::Disconnected | Error::TimedOut => Outcome::fail(Some(format!("{}", e))),
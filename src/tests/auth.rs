@@ -1,8 +1,11 @@
-use crate::connection::AuthState;
+use super::{fresh_connection, restore_registered};
+use crate::connection::{AuthState, Connection};
 use crate::error::Error;
-use crate::globals::GLOBALS;
+use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
+use nostr_types::{Event, EventKind, Signature, Tag, Unixtime};
+use std::ops::Sub;
 use std::time::Duration;
 
 pub async fn prompts_for_auth_initially() -> Result<Outcome, Error> {
@@ -15,7 +18,7 @@ pub async fn prompts_for_auth_initially() -> Result<Outcome, Error> {
         let _ = con
             .as_mut()
             .unwrap()
-            .wait_for_message(Duration::from_secs(WAIT))
+            .wait_for_message(wait())
             .await?;
     }
 
@@ -30,3 +33,305 @@ pub async fn prompts_for_auth_initially() -> Result<Outcome, Error> {
 
     Ok(outcome)
 }
+
+pub async fn sends_single_challenge() -> Result<Outcome, Error> {
+    let lock = GLOBALS.connection.read();
+    let connection = lock.as_ref().unwrap();
+
+    if !connection.dup_auth {
+        return Ok(Outcome::pass(None));
+    }
+
+    let challenges = &connection.challenges;
+    let repeated = challenges
+        .iter()
+        .enumerate()
+        .any(|(i, c)| challenges[..i].contains(c));
+    let note = if repeated {
+        ", some of them repeated"
+    } else {
+        ""
+    };
+    Ok(Outcome::fail(Some(format!(
+        "Sent {} challenges on one connection{}",
+        challenges.len(),
+        note
+    ))))
+}
+
+pub async fn challenge_quality() -> Result<Outcome, Error> {
+    let outcome = challenge_quality_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn challenge_quality_inner() -> Result<Outcome, Error> {
+    // Collect challenges from a couple more connections
+    for _ in 0..2 {
+        fresh_connection().await?;
+        let _ = GLOBALS
+            .connection
+            .write()
+            .as_mut()
+            .unwrap()
+            .wait_for_message(wait())
+            .await?;
+    }
+
+    let lock = GLOBALS.connection.read();
+    let connection = lock.as_ref().unwrap();
+    if connection.challenges.is_empty() {
+        return Ok(Outcome::err("Relay did not challenge us".to_owned()));
+    }
+
+    let mut seen: Vec<&String> = Vec::new();
+    for challenge in connection
+        .past_challenges
+        .iter()
+        .chain(connection.challenges.iter())
+    {
+        if challenge.len() < MIN_CHALLENGE_LEN {
+            return Ok(Outcome::fail(Some(format!(
+                "Challenge {:?} is shorter than {} characters",
+                challenge, MIN_CHALLENGE_LEN
+            ))));
+        }
+        if challenge.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(Outcome::fail(Some(format!(
+                "Challenge {:?} is just a number",
+                challenge
+            ))));
+        }
+        if seen.contains(&challenge) {
+            return Ok(Outcome::fail(Some(format!(
+                "Reused challenge {:?}",
+                challenge
+            ))));
+        }
+        seen.push(challenge);
+    }
+
+    if seen.len() > 1 && seen.windows(2).all(|w| counts_up(w[0], w[1])) {
+        let challenges: Vec<&str> = seen.iter().map(|c| c.as_str()).collect();
+        return Ok(Outcome::fail(Some(format!(
+            "Challenges count up from one connection to the next: {:?}",
+            challenges
+        ))));
+    }
+
+    Ok(Outcome::pass(Some(format!(
+        "{} challenges checked",
+        seen.len()
+    ))))
+}
+
+// As long as 64 random bits written in hex
+const MIN_CHALLENGE_LEN: usize = 16;
+
+// How far a counter or a timestamp (even in microseconds) moves between two
+// of our connections
+const MAX_STEP: u128 = 1_000_000;
+
+// Whether the later challenge is the earlier one with a counter or timestamp
+// moved on: the same apart from the numbers in it, at least one of which
+// went up by a little while the rest stayed put. These checks are about the
+// form of the challenges; they do not measure how random they are.
+fn counts_up(earlier: &str, later: &str) -> bool {
+    let (skeleton_a, numbers_a) = split_numbers(earlier);
+    let (skeleton_b, numbers_b) = split_numbers(later);
+    if skeleton_a != skeleton_b || numbers_a.len() != numbers_b.len() {
+        return false;
+    }
+
+    let mut went_up = false;
+    for (a, b) in numbers_a.iter().zip(numbers_b.iter()) {
+        match (a, b) {
+            (Some(a), Some(b)) if b > a && b - a <= MAX_STEP => went_up = true,
+            (a, b) if a == b => {}
+            _ => return false,
+        }
+    }
+    went_up
+}
+
+// The challenge with each run of digits replaced by '#', and the values of
+// those runs (None if too long to be a counter)
+fn split_numbers(challenge: &str) -> (String, Vec<Option<u128>>) {
+    let mut skeleton = String::new();
+    let mut numbers: Vec<Option<u128>> = Vec::new();
+    let mut digits = String::new();
+    for c in challenge.chars().chain(std::iter::once(' ')) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            skeleton.push('#');
+            numbers.push(digits.parse().ok());
+            digits.clear();
+        }
+        skeleton.push(c);
+    }
+    skeleton.pop();
+    (skeleton, numbers)
+}
+
+// The tests below each start a fresh connection, so that they can answer the
+// challenge however they like, then put the registered connection back.
+
+pub async fn rejects_wrong_challenge() -> Result<Outcome, Error> {
+    bad_auth(|relay_url, _challenge| {
+        Connection::make_auth_event(relay_url, "not-the-challenge", true)
+    })
+    .await
+}
+
+pub async fn rejects_wrong_relay_tag() -> Result<Outcome, Error> {
+    bad_auth(|_relay_url, challenge| {
+        Connection::make_auth_event("wss://some.other.relay/", challenge, true)
+    })
+    .await
+}
+
+pub async fn rejects_stale_timestamp() -> Result<Outcome, Error> {
+    bad_auth(|relay_url, challenge| {
+        Globals::make_event(
+            EventParts::Dated(
+                EventKind::Auth,
+                auth_tags(relay_url, challenge),
+                "".to_string(),
+                Unixtime::now().sub(Duration::from_secs(20 * 60)),
+            ),
+            true,
+        )
+    })
+    .await
+}
+
+pub async fn rejects_wrong_kind() -> Result<Outcome, Error> {
+    bad_auth(|relay_url, challenge| {
+        Globals::make_event(
+            EventParts::Basic(
+                EventKind::TextNote,
+                auth_tags(relay_url, challenge),
+                "".to_string(),
+            ),
+            true,
+        )
+    })
+    .await
+}
+
+pub async fn rejects_bad_signature() -> Result<Outcome, Error> {
+    bad_auth(|relay_url, challenge| {
+        let mut event = Connection::make_auth_event(relay_url, challenge, true)?;
+        event.sig = Signature::zeroes();
+        Ok(event)
+    })
+    .await
+}
+
+pub async fn reauth_with_second_key() -> Result<Outcome, Error> {
+    let outcome = reauth_with_second_key_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn reauth_with_second_key_inner() -> Result<Outcome, Error> {
+    fresh_connection().await?;
+
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+
+    if connection
+        .authenticate(true, wait())
+        .await?
+        != AuthState::Success
+    {
+        return Ok(Outcome::err("Could not authenticate".to_owned()));
+    }
+
+    // Answer the same challenge again, as the stranger
+    let challenge = connection.challenges.last().cloned().unwrap_or_default();
+    let event = Connection::make_auth_event(&connection.relay_url, &challenge, false)?;
+    match connection
+        .authenticate_with(event, wait())
+        .await?
+    {
+        AuthState::Success => Ok(Outcome::pass(None)),
+        AuthState::Failure(reason) => Ok(Outcome::fail(Some(reason))),
+        _ => Ok(Outcome::fail(Some(
+            "No response to the second AUTH".to_owned(),
+        ))),
+    }
+}
+
+pub async fn rejects_unsolicited_auth() -> Result<Outcome, Error> {
+    let outcome = rejects_unsolicited_auth_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn rejects_unsolicited_auth_inner() -> Result<Outcome, Error> {
+    fresh_connection().await?;
+
+    // Send AUTH straight away, before reading anything from the relay
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+    let event = Connection::make_auth_event(&connection.relay_url, "unsolicited", true)?;
+    match connection
+        .authenticate_with(event, wait())
+        .await?
+    {
+        AuthState::Success => Ok(Outcome::fail(Some(
+            "Accepted AUTH for a challenge it never sent".to_owned(),
+        ))),
+        AuthState::Failure(reason) => Ok(Outcome::pass(Some(reason))),
+        _ => Ok(Outcome::fail(Some("No response to AUTH".to_owned()))),
+    }
+}
+
+// Get a challenge on a fresh connection and answer it with the event that
+// make_event builds. The relay must refuse it.
+async fn bad_auth<F>(make_event: F) -> Result<Outcome, Error>
+where
+    F: Fn(&str, &str) -> Result<Event, Error>,
+{
+    let outcome = bad_auth_inner(make_event).await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn bad_auth_inner<F>(make_event: F) -> Result<Outcome, Error>
+where
+    F: Fn(&str, &str) -> Result<Event, Error>,
+{
+    fresh_connection().await?;
+
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+    let _ = connection
+        .wait_for_message(wait())
+        .await?;
+    let challenge = match &connection.auth_state {
+        AuthState::Challenged(challenge) => challenge.clone(),
+        _ => return Ok(Outcome::err("Relay did not challenge us".to_owned())),
+    };
+
+    let event = make_event(&connection.relay_url, &challenge)?;
+    match connection
+        .authenticate_with(event, wait())
+        .await?
+    {
+        AuthState::Success => Ok(Outcome::fail(Some("Accepted the AUTH".to_owned()))),
+        AuthState::Failure(reason) => Ok(Outcome::pass(Some(reason))),
+        _ => Ok(Outcome::fail(Some("No response to AUTH".to_owned()))),
+    }
+}
+
+fn auth_tags(relay_url: &str, challenge: &str) -> Vec<Tag> {
+    vec![
+        Tag::new(&["relay", relay_url]),
+        Tag::new(&["challenge", challenge]),
+    ]
+}
@@ -1,9 +1,9 @@
 use super::{minutes_ago, tags};
-use crate::WAIT;
+use crate::wait;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use nostr_types::{EventKind, Filter, NAddr};
+use nostr_types::{Event, EventKind, Filter, Id, NAddr};
 use std::time::Duration;
 
 pub async fn delete_by_id() -> Result<Outcome, Error> {
@@ -20,7 +20,7 @@ pub async fn delete_by_id() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -42,7 +42,7 @@ pub async fn delete_by_id() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(delete_event, Duration::from_secs(WAIT))
+        .post_event(delete_event, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -56,7 +56,7 @@ pub async fn delete_by_id() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -101,7 +101,7 @@ pub async fn delete_by_addr() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -125,7 +125,7 @@ pub async fn delete_by_addr() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(delete_event, Duration::from_secs(WAIT))
+        .post_event(delete_event, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -139,7 +139,7 @@ pub async fn delete_by_addr() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -190,7 +190,7 @@ pub async fn delete_by_addr_only_older() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event1, Duration::from_secs(WAIT))
+        .post_event(event1, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -216,7 +216,7 @@ pub async fn delete_by_addr_only_older() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event3, Duration::from_secs(WAIT))
+        .post_event(event3, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -243,7 +243,7 @@ pub async fn delete_by_addr_only_older() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(delete_event, Duration::from_secs(WAIT))
+        .post_event(delete_event, wait())
         .await?;
     if !ok {
         return Ok(Outcome::err(reason));
@@ -257,7 +257,7 @@ pub async fn delete_by_addr_only_older() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -272,25 +272,611 @@ pub async fn delete_by_addr_only_older() -> Result<Outcome, Error> {
 }
 
 pub async fn delete_by_addr_bound_by_tag() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // Make three events: the target, one with a different d-tag, and one with
+    // the same d-tag under a different kind
+    let target = Globals::make_event(
+        EventParts::Basic(
+            EventKind::LongFormContent,
+            tags(&[&["d", "delete_by_addr_bound_by_tag_test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let other_d = Globals::make_event(
+        EventParts::Basic(
+            EventKind::LongFormContent,
+            tags(&[&["d", "delete_by_addr_bound_by_tag_test_other"]]),
+            "I say right thing".to_string(),
+        ),
+        true,
+    )?;
+    let other_kind = Globals::make_event(
+        EventParts::Basic(
+            EventKind::AppSpecificData,
+            tags(&[&["d", "delete_by_addr_bound_by_tag_test"]]),
+            "I say right thing".to_string(),
+        ),
+        true,
+    )?;
+    let ids = [target.id, other_d.id, other_kind.id];
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &target,
+        "delete_by_addr_bound_by_tag_test",
+    );
+
+    // Submit them
+    for event in [target, other_d, other_kind] {
+        let (ok, reason) = post(event).await?;
+        if !ok {
+            return Ok(Outcome::err(reason));
+        }
+    }
+
+    // Delete the target by a-tag
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["a", &a_tag]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let events = fetch_by_ids(&ids).await?;
+    if events.iter().any(|e| e.id == ids[0]) {
+        Ok(Outcome::fail(Some(
+            "Failed to delete event by a-tag".to_owned(),
+        )))
+    } else if !events.iter().any(|e| e.id == ids[1]) {
+        Ok(Outcome::fail(Some(
+            "Also deleted an event with a different d-tag".to_owned(),
+        )))
+    } else if !events.iter().any(|e| e.id == ids[2]) {
+        Ok(Outcome::fail(Some(
+            "Also deleted an event of a different kind".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
 }
 
 pub async fn delete_by_id_of_others() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // The stranger tries to delete the registered user's event
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say right thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Whether the relay accepts the deletion event itself does not matter,
+    // only whether it acts on it
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        false,
+    )?;
+    let _ = post(delete_event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        return Ok(Outcome::fail(Some(
+            "Stranger deleted the registered user's event".to_owned(),
+        )));
+    }
+
+    // And vice versa, if the relay takes events from the stranger at all
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say right thing".to_string(),
+        ),
+        false,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::pass(Some(format!(
+            "Did not check deleting the stranger's event by id, it was refused: {reason}"
+        ))));
+    }
+
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let _ = post(delete_event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        Ok(Outcome::fail(Some(
+            "Registered user deleted the stranger's event".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
 }
 
 pub async fn delete_by_addr_of_others() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // The stranger tries to delete the registered user's event by its address
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::LongFormContent,
+            tags(&[&["d", "delete_by_addr_of_others_test"]]),
+            "I say right thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &event,
+        "delete_by_addr_of_others_test",
+    );
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["a", &a_tag]]),
+            "".to_string(),
+        ),
+        false,
+    )?;
+    let _ = post(delete_event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        return Ok(Outcome::fail(Some(
+            "Stranger deleted the registered user's event by a-tag".to_owned(),
+        )));
+    }
+
+    // And vice versa, if the relay takes events from the stranger at all
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::LongFormContent,
+            tags(&[&["d", "delete_by_addr_of_others_test"]]),
+            "I say right thing".to_string(),
+        ),
+        false,
+    )?;
+    let event_id = event.id;
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &event,
+        "delete_by_addr_of_others_test",
+    );
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::pass(Some(format!(
+            "Did not check deleting the stranger's event by a-tag, it was refused: {reason}"
+        ))));
+    }
+
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["a", &a_tag]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let _ = post(delete_event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        Ok(Outcome::fail(Some(
+            "Registered user deleted the stranger's event by a-tag".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
 }
 
 pub async fn resubmission_of_delete_by_id() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // Make and submit an event
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event.clone()).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Delete it by id
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Submit it again. Either an OK of false or an OK of true without storing
+    // it is fine, so long as it is not served.
+    let _ = post(event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Resubmitted deleted event was served".to_owned(),
+        )))
+    }
 }
 
 pub async fn resubmission_of_older_delete_by_addr() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // Make and submit an event, older than the deletion
+    let event = Globals::make_event(
+        EventParts::Dated(
+            EventKind::LongFormContent,
+            tags(&[&["d", "resubmission_of_older_delete_by_addr_test"]]),
+            "I say wrong thing".to_string(),
+            minutes_ago(3),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &event,
+        "resubmission_of_older_delete_by_addr_test",
+    );
+    let (ok, reason) = post(event.clone()).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Delete it by a-tag
+    let delete_event = Globals::make_event(
+        EventParts::Dated(
+            EventKind::EventDeletion,
+            tags(&[&["a", &a_tag]]),
+            "".to_string(),
+            minutes_ago(1),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Submit it again
+    let _ = post(event).await?;
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Resubmitted event older than the a-tag deletion was served".to_owned(),
+        )))
+    }
 }
 
 pub async fn resubmission_of_newer_delete_by_addr() -> Result<Outcome, Error> {
-    Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
+    // Make and submit an event, older than the deletion
+    let event = Globals::make_event(
+        EventParts::Dated(
+            EventKind::LongFormContent,
+            tags(&[&["d", "resubmission_of_newer_delete_by_addr_test"]]),
+            "I say wrong thing".to_string(),
+            minutes_ago(3),
+        ),
+        true,
+    )?;
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &event,
+        "resubmission_of_newer_delete_by_addr_test",
+    );
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Delete it by a-tag
+    let delete_event = Globals::make_event(
+        EventParts::Dated(
+            EventKind::EventDeletion,
+            tags(&[&["a", &a_tag]]),
+            "".to_string(),
+            minutes_ago(2),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Submit a new version at the same address, newer than the deletion
+    let newer = Globals::make_event(
+        EventParts::Dated(
+            EventKind::LongFormContent,
+            tags(&[&["d", "resubmission_of_newer_delete_by_addr_test"]]),
+            "I say right thing".to_string(),
+            minutes_ago(1),
+        ),
+        true,
+    )?;
+    let newer_id = newer.id;
+    let (ok, reason) = post(newer).await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(format!(
+            "Rejected event newer than the a-tag deletion: {reason}"
+        ))));
+    }
+
+    if fetch_by_ids(&[newer_id]).await?.is_empty() {
+        Ok(Outcome::fail(Some(
+            "Event newer than the a-tag deletion was not served".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+pub async fn deletion_of_deletion() -> Result<Outcome, Error> {
+    // Make, submit and delete an event
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let delete_id = delete_event.id;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Try to delete the deletion. Per NIP-09 this has no effect, so the relay
+    // may reject it or accept it but it must not bring the event back.
+    let undelete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &delete_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let _ = post(undelete_event).await?;
+
+    let events = fetch_by_ids(&[event_id, delete_id]).await?;
+    if events.iter().any(|e| e.id == event_id) {
+        Ok(Outcome::fail(Some(
+            "Deleting the deletion brought the event back".to_owned(),
+        )))
+    } else if !events.iter().any(|e| e.id == delete_id) {
+        Ok(Outcome::fail(Some("Deleted a deletion request".to_owned())))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+pub async fn delete_with_k_tags() -> Result<Outcome, Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Delete it, naming its kind in a k-tag
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()], &["k", "1"]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(format!(
+            "Rejected deletion with a k-tag: {reason}"
+        ))));
+    }
+
+    if fetch_by_ids(&[event_id]).await?.is_empty() {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Deleted event did not get deleted".to_owned(),
+        )))
+    }
+}
+
+pub async fn delete_by_id_and_addr_together() -> Result<Outcome, Error> {
+    let note = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let article = Globals::make_event(
+        EventParts::Basic(
+            EventKind::LongFormContent,
+            tags(&[&["d", "delete_by_id_and_addr_together_test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let ids = [note.id, article.id];
+    let a_tag = a_tag_of(
+        EventKind::LongFormContent,
+        &article,
+        "delete_by_id_and_addr_together_test",
+    );
+    for event in [note, article] {
+        let (ok, reason) = post(event).await?;
+        if !ok {
+            return Ok(Outcome::err(reason));
+        }
+    }
+
+    // One deletion request covering both
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &ids[0].as_hex_string()], &["a", &a_tag]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let events = fetch_by_ids(&ids).await?;
+    if events.iter().any(|e| e.id == ids[0]) {
+        Ok(Outcome::fail(Some(
+            "Did not delete the e-tagged event".to_owned(),
+        )))
+    } else if events.iter().any(|e| e.id == ids[1]) {
+        Ok(Outcome::fail(Some(
+            "Did not delete the a-tagged event".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+pub async fn serves_deletion_requests() -> Result<Outcome, Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I say wrong thing".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+    let (ok, reason) = post(event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+    let delete_event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::EventDeletion,
+            tags(&[&["e", &event_id.as_hex_string()]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let delete_id = delete_event.id;
+    let (ok, reason) = post(delete_event).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    // Clients which already hold the event find the deletion by its e-tag
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::EventDeletion];
+    filter.add_tag_value('e', event_id.as_hex_string());
+    let events = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events();
+
+    if events.iter().any(|e| e.id == delete_id) {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Deletion request was not served".to_owned(),
+        )))
+    }
+}
+
+fn a_tag_of(kind: EventKind, event: &Event, d: &str) -> String {
+    format!(
+        "{}:{}:{}",
+        Into::<u32>::into(kind),
+        event.pubkey.as_hex_string(),
+        d
+    )
+}
+
+async fn post(event: Event) -> Result<(bool, String), Error> {
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event, wait())
+        .await
+}
+
+async fn fetch_by_ids(ids: &[Id]) -> Result<Vec<Event>, Error> {
+    let mut filter = Filter::new();
+    filter.ids = ids.iter().map(|id| (*id).into()).collect();
+    Ok(GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events())
 }
@@ -1,9 +1,8 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{EventKind, Filter, Id, PrivateKey, Unixtime};
-use std::time::Duration;
 
 pub async fn supports_eose() -> Result<Outcome, Error> {
     // A very benign filter.
@@ -23,7 +22,7 @@ pub async fn supports_eose() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?;
 
     match (fresult.close_msg, fresult.post_eose_events) {
@@ -55,7 +54,7 @@ pub async fn closes_complete_subscriptions_after_eose() -> Result<Outcome, Error
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?;
 
     match (fresult.close_msg, fresult.post_eose_events) {
@@ -88,7 +87,7 @@ pub async fn keeps_open_incomplete_subscriptions_after_eose() -> Result<Outcome,
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?;
 
     match (fresult.close_msg, fresult.post_eose_events) {
@@ -3,9 +3,8 @@ use crate::connection::Connection;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{EventKind, Filter, PublicKeyHex, Signer};
-use std::time::Duration;
 
 pub async fn ephemeral_subscriptions_work() -> Result<Outcome, Error> {
     let filter = {
@@ -23,7 +22,7 @@ pub async fn ephemeral_subscriptions_work() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events_keep_open(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events_keep_open(vec![filter], wait())
         .await?;
     let sub_id = fresult.sub_id.unwrap();
 
@@ -49,7 +48,7 @@ pub async fn ephemeral_subscriptions_work() -> Result<Outcome, Error> {
         true,
     )?;
     let (ok, reason) = injector
-        .post_event(event.clone(), Duration::from_secs(WAIT))
+        .post_event(event.clone(), wait())
         .await?;
     if !ok {
         return Ok(Outcome::fail(Some(format!(
@@ -64,7 +63,7 @@ pub async fn ephemeral_subscriptions_work() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .collect_events(sub_id, Duration::from_secs(WAIT))
+        .collect_events(sub_id, wait())
         .await?;
 
     if collected_events.is_empty() {
@@ -95,7 +94,7 @@ pub async fn persists_ephemeral_events() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event.clone(), Duration::from_secs(WAIT))
+        .post_event(event.clone(), wait())
         .await?;
     if !ok {
         return Ok(Outcome::fail(Some(format!(
@@ -118,7 +117,7 @@ pub async fn persists_ephemeral_events() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -1,10 +1,10 @@
-use super::tags;
-use crate::WAIT;
+use super::{maybe_submit_event_group_a, reconnect_registered, tags};
+use crate::connection::FetchResult;
+use crate::wait;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use nostr_types::{EventKind, Filter, Signer, Unixtime};
-use std::time::Duration;
+use nostr_types::{Event, EventKind, Filter, Signer, Unixtime};
 
 pub async fn since_until_are_inclusive() -> Result<Outcome, Error> {
     let time = Unixtime::now();
@@ -18,7 +18,7 @@ pub async fn since_until_are_inclusive() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if !ok {
@@ -43,7 +43,7 @@ pub async fn since_until_are_inclusive() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![until_filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![until_filter], wait())
         .await?
         .into_events();
 
@@ -52,7 +52,7 @@ pub async fn since_until_are_inclusive() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![since_filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![since_filter], wait())
         .await?
         .into_events();
 
@@ -79,7 +79,7 @@ pub async fn limit_zero() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?;
 
     if !fetch_result.pre_eose_events.is_empty() {
@@ -92,3 +92,316 @@ pub async fn limit_zero() -> Result<Outcome, Error> {
         Ok(Outcome::pass(None))
     }
 }
+
+pub async fn kind_larger_than_16bit() -> Result<Outcome, Error> {
+    // A relay truncating filter kinds to 16 bits would treat 65537 as kind 1,
+    // and the registered user has plenty of kind 1 events by now.
+    let pk = GLOBALS.registered_user.read().public_key().as_hex_string();
+    let fresult = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events_raw(
+            &[&format!(r#"{{"authors":["{}"],"kinds":[65537]}}"#, pk)],
+            wait(),
+        )
+        .await?;
+
+    let close_msg = fresult.close_msg.clone();
+    let eose_happened = fresult.post_eose_events.is_some();
+    let events = fresult.into_events();
+
+    if let Some(e) = events.iter().find(|e| Into::<u32>::into(e.kind) != 65537) {
+        Ok(Outcome::fail(Some(format!(
+            "Returned an event of kind {}",
+            Into::<u32>::into(e.kind)
+        ))))
+    } else if let Some(msg) = close_msg {
+        Ok(Outcome::pass(Some(format!("Rejected: {msg}"))))
+    } else if !eose_happened {
+        Ok(Outcome::fail(Some("Timed out without EOSE".to_owned())))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+// Relays may either handle negative since/until or reject them with CLOSED.
+// Silence, dropping the connection, or the wrong events are failures.
+pub async fn negative_created_at() -> Result<Outcome, Error> {
+    let pk = GLOBALS.registered_user.read().public_key().as_hex_string();
+
+    // A window entirely before 1970
+    let filter = format!(r#"{{"authors":["{}"],"since":-1000,"until":-1}}"#, pk);
+    let fresult = match fetch_negative(&filter).await? {
+        Ok(fresult) => fresult,
+        Err(outcome) => return Ok(outcome),
+    };
+    if fresult
+        .into_events()
+        .iter()
+        .any(|e| e.created_at.0 < -1000 || e.created_at.0 > -1)
+    {
+        return Ok(Outcome::fail(Some(
+            "Returned events outside of a negative since/until window".to_owned(),
+        )));
+    }
+
+    // A negative since on its own should not exclude anything
+    let filter = format!(r#"{{"authors":["{}"],"since":-1000,"limit":1}}"#, pk);
+    let fresult = match fetch_negative(&filter).await? {
+        Ok(fresult) => fresult,
+        Err(outcome) => return Ok(outcome),
+    };
+    if fresult.into_events().is_empty() {
+        return Ok(Outcome::fail(Some(
+            "Negative since excluded events it should have matched".to_owned(),
+        )));
+    }
+
+    Ok(Outcome::pass(None))
+}
+
+// The result of a filter with negative since/until, or the outcome of the
+// test (the inner Err) if the relay rejected it or did not answer
+async fn fetch_negative(filter: &str) -> Result<Result<FetchResult, Outcome>, Error> {
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events_raw(&[filter], wait())
+        .await;
+
+    let fresult = match result {
+        Ok(fresult) => fresult,
+        Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+            reconnect_registered().await?;
+            return Ok(Err(Outcome::fail(Some(
+                "Dropped the connection".to_owned(),
+            ))));
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some(msg) = fresult.close_msg {
+        return Ok(Err(Outcome::pass(Some(format!(
+            "Rejected negative since/until: {msg}"
+        )))));
+    }
+    if fresult.post_eose_events.is_none() {
+        return Ok(Err(Outcome::fail(Some(
+            "Timed out without EOSE".to_owned(),
+        ))));
+    }
+
+    Ok(Ok(fresult))
+}
+
+pub async fn prefixes() -> Result<Outcome, Error> {
+    maybe_submit_event_group_a().await?;
+
+    let event = GLOBALS
+        .event_group_a
+        .read()
+        .get("limit_test_first")
+        .unwrap()
+        .0;
+
+    let id_hex = event.id.as_hex_string();
+    let by_id = prefix_query("ids", &id_hex[..10], &event).await?;
+    if by_id.pass != Some(true) {
+        return Ok(by_id);
+    }
+
+    let pk_hex = event.pubkey.as_hex_string();
+    let by_author = prefix_query("authors", &pk_hex[..10], &event).await?;
+    if by_author.pass != Some(true) {
+        return Ok(by_author);
+    }
+
+    Ok(Outcome::pass(Some(format!(
+        "ids: {}; authors: {}",
+        by_id.info.unwrap_or_default(),
+        by_author.info.unwrap_or_default()
+    ))))
+}
+
+// Prefix matching was removed from NIP-01. Relays may still support it or
+// may reject it, but must not return events that do not match the prefix.
+async fn prefix_query(field: &str, prefix: &str, target: &Event) -> Result<Outcome, Error> {
+    let fresult = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events_raw(
+            &[&format!(r#"{{"{}":["{}"],"limit":10}}"#, field, prefix)],
+            wait(),
+        )
+        .await?;
+
+    let close_msg = fresult.close_msg.clone();
+    let eose_happened = fresult.post_eose_events.is_some();
+    let events = fresult.into_events();
+
+    let hex_of = |e: &Event| {
+        if field == "ids" {
+            e.id.as_hex_string()
+        } else {
+            e.pubkey.as_hex_string()
+        }
+    };
+
+    if events.iter().any(|e| !hex_of(e).starts_with(prefix)) {
+        Ok(Outcome::fail(Some(format!(
+            "{field} prefix returned events that do not match the prefix"
+        ))))
+    } else if events.iter().any(|e| hex_of(e) == hex_of(target)) {
+        Ok(Outcome::pass(Some("supported".to_owned())))
+    } else if let Some(msg) = close_msg {
+        Ok(Outcome::pass(Some(format!("rejected: {msg}"))))
+    } else if !eose_happened {
+        Ok(Outcome::fail(Some(format!(
+            "{field} prefix was neither answered nor rejected"
+        ))))
+    } else {
+        Ok(Outcome::fail(Some(format!(
+            "{field} prefix silently matched nothing"
+        ))))
+    }
+}
+
+pub async fn unknown_fields() -> Result<Outcome, Error> {
+    maybe_submit_event_group_a().await?;
+
+    let id = GLOBALS
+        .event_group_a
+        .read()
+        .get("limit_test_first")
+        .unwrap()
+        .0
+        .id;
+
+    let fresult = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events_raw(
+            &[&format!(
+                r#"{{"ids":["{}"],"unknown_field":"x","kinds_":[1]}}"#,
+                id.as_hex_string()
+            )],
+            wait(),
+        )
+        .await?;
+
+    let close_msg = fresult.close_msg.clone();
+    let eose_happened = fresult.post_eose_events.is_some();
+    let events = fresult.into_events();
+
+    if events.iter().any(|e| e.id == id) {
+        Ok(Outcome::pass(Some("ignored".to_owned())))
+    } else if let Some(msg) = close_msg {
+        Ok(Outcome::pass(Some(format!("rejected: {msg}"))))
+    } else if !eose_happened {
+        Ok(Outcome::fail(Some("Timed out without EOSE".to_owned())))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Unknown fields silently caused the filter to match nothing".to_owned(),
+        )))
+    }
+}
+
+pub async fn multi_letter_tags() -> Result<Outcome, Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["tt", "multi_letter_tag_test"]]),
+            "".to_string(),
+        ),
+        true,
+    )?;
+    let event_id = event.id;
+
+    let (ok, reason) = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event, wait())
+        .await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let pk = GLOBALS.registered_user.read().public_key().as_hex_string();
+    let fresult = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events_raw(
+            &[&format!(
+                r##"{{"authors":["{}"],"#tt":["multi_letter_tag_test"]}}"##,
+                pk
+            )],
+            wait(),
+        )
+        .await?;
+
+    let close_msg = fresult.close_msg.clone();
+    let eose_happened = fresult.post_eose_events.is_some();
+    let events = fresult.into_events();
+
+    let has_tag = |e: &Event| {
+        e.tags
+            .iter()
+            .any(|t| t.tagname() == "tt" && t.value() == "multi_letter_tag_test")
+    };
+
+    if events.iter().any(|e| !has_tag(e)) {
+        Ok(Outcome::fail(Some(
+            "Ignored the multi-letter tag constraint".to_owned(),
+        )))
+    } else if events.iter().any(|e| e.id == event_id) {
+        Ok(Outcome::pass(Some("supported".to_owned())))
+    } else if let Some(msg) = close_msg {
+        Ok(Outcome::pass(Some(format!("rejected: {msg}"))))
+    } else if !eose_happened {
+        Ok(Outcome::fail(Some("Timed out without EOSE".to_owned())))
+    } else {
+        Ok(Outcome::pass(Some("not indexed".to_owned())))
+    }
+}
+
+pub async fn empty_arrays_match_nothing() -> Result<Outcome, Error> {
+    let pk = GLOBALS.registered_user.read().public_key().as_hex_string();
+
+    for field in ["ids", "kinds"] {
+        let fresult = GLOBALS
+            .connection
+            .write()
+            .as_mut()
+            .unwrap()
+            .fetch_events_raw(
+                &[&format!(r#"{{"authors":["{}"],"{}":[]}}"#, pk, field)],
+                wait(),
+            )
+            .await?;
+
+        if fresult.close_msg.is_none() && fresult.post_eose_events.is_none() {
+            return Ok(Outcome::fail(Some(format!(
+                "Timed out without EOSE on empty {field}"
+            ))));
+        }
+        if !fresult.into_events().is_empty() {
+            return Ok(Outcome::fail(Some(format!(
+                "Empty {field} was treated as no constraint"
+            ))));
+        }
+    }
+
+    Ok(Outcome::pass(None))
+}
@@ -2,9 +2,8 @@ use super::maybe_submit_event_group_a;
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{Event, EventKind, Filter, IdHex, PublicKeyHex, Signer, Unixtime};
-use std::time::Duration;
 
 pub async fn newest_to_oldest() -> Result<Outcome, Error> {
     maybe_submit_event_group_a().await?;
@@ -29,7 +28,7 @@ pub async fn newest_to_oldest() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -70,7 +69,7 @@ pub async fn newest_events_when_limited() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -243,7 +242,7 @@ async fn find(filter: Filter, num_matches_expected: Option<usize>) -> Result<Out
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter.clone()], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter.clone()], wait())
         .await?;
     let maybe_error = fresult.close_msg.clone();
     let fetched = fresult.into_events();
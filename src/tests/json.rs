@@ -1,9 +1,9 @@
 use crate::error::Error;
-use crate::globals::{Globals, GLOBALS};
+use crate::globals::{Globals, GLOBALS, NIP01_FIELD_ORDER};
 use crate::outcome::Outcome;
-use crate::WAIT;
-use nostr_types::Unixtime;
-use std::time::Duration;
+use crate::wait;
+use nostr_types::{Filter, Id, Unixtime};
+use serde_json::Value;
 
 // Try including all nip01 escape sequences
 pub async fn nip1() -> Result<Outcome, Error> {
@@ -38,7 +38,7 @@ async fn go(content: &str) -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_raw_event(id, raw_event, Duration::from_secs(WAIT))
+        .post_raw_event(id, raw_event, wait())
         .await?;
 
     if ok {
@@ -47,3 +47,144 @@ async fn go(content: &str) -> Result<Outcome, Error> {
         Ok(Outcome::fail(Some(reason)))
     }
 }
+
+pub async fn field_order() -> Result<Outcome, Error> {
+    let order = [
+        "sig",
+        "content",
+        "tags",
+        "kind",
+        "created_at",
+        "pubkey",
+        "id",
+    ];
+    let (id, raw_event) = Globals::make_raw_event_custom(
+        &format!("{}", Unixtime::now().0),
+        "1",
+        "[]",
+        "field order test",
+        true,
+        &order,
+        &[],
+    );
+
+    let frame = match post_and_fetch_raw(id, raw_event.clone()).await? {
+        Ok(frame) => frame,
+        Err(outcome) => return Ok(outcome),
+    };
+
+    if frame.contains(&raw_event) {
+        return Ok(Outcome::pass(Some("byte-identical".to_owned())));
+    }
+
+    let positions: Vec<Option<usize>> = order
+        .iter()
+        .map(|field| frame.find(&format!("\"{}\":", field)))
+        .collect();
+    let preserved = positions
+        .windows(2)
+        .all(|w| matches!((w[0], w[1]), (Some(a), Some(b)) if a < b));
+
+    if preserved {
+        Ok(Outcome::pass(Some(
+            "order preserved, but not byte-identical".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::fail(Some(format!(
+            "Field order was not preserved: {}",
+            frame
+        ))))
+    }
+}
+
+pub async fn nonstandard_fields() -> Result<Outcome, Error> {
+    let nonstandard = r#"{"a":[1,"b"],"c":null}"#;
+    let (id, raw_event) = Globals::make_raw_event_custom(
+        &format!("{}", Unixtime::now().0),
+        "1",
+        "[]",
+        "nonstandard field test",
+        true,
+        &NIP01_FIELD_ORDER,
+        &[("nonstandard", nonstandard)],
+    );
+
+    let frame = match post_and_fetch_raw(id, raw_event.clone()).await? {
+        Ok(frame) => frame,
+        Err(outcome) => return Ok(outcome),
+    };
+
+    if frame.contains(&raw_event) {
+        return Ok(Outcome::pass(Some("byte-identical".to_owned())));
+    }
+
+    let value: Value = serde_json::from_str(&frame)?;
+    let expected: Value = serde_json::from_str(nonstandard)?;
+    if value[2]["nonstandard"] == expected {
+        Ok(Outcome::pass(Some(
+            "preserved, but not byte-identical".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Non-standard field was not preserved".to_owned(),
+        )))
+    }
+}
+
+pub async fn null_characters() -> Result<Outcome, Error> {
+    let (id, raw_event) = Globals::make_raw_event(
+        &format!("{}", Unixtime::now().0),
+        "1",
+        "[]",
+        r#"before\u0000after"#,
+        true,
+    );
+
+    let frame = match post_and_fetch_raw(id, raw_event).await? {
+        Ok(frame) => frame,
+        Err(outcome) => return Ok(outcome),
+    };
+
+    let value: Value = serde_json::from_str(&frame)?;
+    if value[2]["content"].as_str() == Some("before\u{0}after") {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(format!(
+            "Null character did not round-trip: {}",
+            value[2]["content"]
+        ))))
+    }
+}
+
+// Post a raw event and then fetch it back by id, returning the relay's EVENT
+// frame exactly as it came over the wire. Fails the test (the inner Err) if
+// the event is rejected or cannot be fetched back.
+async fn post_and_fetch_raw(id: Id, raw_event: String) -> Result<Result<String, Outcome>, Error> {
+    let (ok, reason) = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event(id, raw_event, wait())
+        .await?;
+    if !ok {
+        return Ok(Err(Outcome::fail(Some(reason))));
+    }
+
+    let mut filter = Filter::new();
+    filter.ids = vec![id.into()];
+    let fresult = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?;
+
+    match fresult.raw_event(id) {
+        Some(frame) => Ok(Ok(frame.to_owned())),
+        None => Ok(Err(Outcome::fail(Some(
+            "Accepted the event but did not serve it back".to_owned(),
+        )))),
+    }
+}
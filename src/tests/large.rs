@@ -0,0 +1,121 @@
+use super::{reconnect_registered, tags};
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::{Event, EventKind, Filter, PrivateKey, Tag};
+
+pub async fn contact_list(entries: usize, must_accept: bool) -> Result<Outcome, Error> {
+    let mut p_tags: Vec<Tag> = Vec::with_capacity(entries);
+    for _ in 0..entries {
+        let pubkey = PrivateKey::generate().public_key();
+        p_tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+    }
+
+    let event = Globals::make_event(
+        EventParts::Basic(EventKind::ContactList, p_tags, "".to_owned()),
+        true,
+    )?;
+
+    stress(event, must_accept).await
+}
+
+pub async fn content(bytes: usize) -> Result<Outcome, Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(EventKind::TextNote, tags(&[]), "x".repeat(bytes)),
+        true,
+    )?;
+
+    stress(event, false).await
+}
+
+pub async fn many_tags(num_tags: usize, num_values: usize) -> Result<Outcome, Error> {
+    let mut many: Vec<Tag> = Vec::with_capacity(num_tags);
+    for t in 0..num_tags {
+        let values: Vec<String> = std::iter::once("t".to_owned())
+            .chain((0..num_values).map(|v| format!("tag{}value{}", t, v)))
+            .collect();
+        let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+        many.push(Tag::new(&values));
+    }
+
+    let event = Globals::make_event(
+        EventParts::Basic(EventKind::TextNote, many, "many tags".to_owned()),
+        true,
+    )?;
+
+    stress(event, false).await
+}
+
+// Submit a large event and read it back. Relays may refuse events over their
+// limits, but should say so with a machine-readable OK rather than by
+// dropping the connection.
+async fn stress(event: Event, must_accept: bool) -> Result<Outcome, Error> {
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event.clone(), wait())
+        .await;
+
+    let (ok, reason) = match result {
+        Ok(r) => r,
+        Err(Error::Disconnected) => {
+            reconnect_registered().await?;
+            return Ok(Outcome::fail(Some(
+                "Dropped the connection instead of answering".to_owned(),
+            )));
+        }
+        Err(Error::Websocket(e)) => {
+            reconnect_registered().await?;
+            return Ok(Outcome::fail(Some(format!(
+                "Websocket failed instead of answering: {e}"
+            ))));
+        }
+        Err(e) => return Err(e),
+    };
+
+    if !ok {
+        return if !has_prefix(&reason) {
+            Ok(Outcome::fail(Some(format!(
+                "Rejected without a machine-readable prefix: {reason}"
+            ))))
+        } else if must_accept {
+            Ok(Outcome::fail(Some(reason)))
+        } else {
+            Ok(Outcome::pass(Some(format!("Rejected: {reason}"))))
+        };
+    }
+
+    let mut filter = Filter::new();
+    filter.ids = vec![event.id.into()];
+    let events = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events();
+
+    match events.first() {
+        None => Ok(Outcome::fail(Some(
+            "Accepted the event but did not serve it back".to_owned(),
+        ))),
+        Some(e) if *e != event => Ok(Outcome::fail(Some(
+            "Served back an altered event".to_owned(),
+        ))),
+        Some(_) => Ok(Outcome::pass(None)),
+    }
+}
+
+// NIP-01 OK messages start with a machine-readable prefix like "invalid: "
+fn has_prefix(reason: &str) -> bool {
+    match reason.split_once(':') {
+        Some((prefix, _)) => {
+            !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        }
+        None => false,
+    }
+}
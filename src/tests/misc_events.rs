@@ -1,9 +1,8 @@
 use crate::error::Error;
 use crate::globals::{Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
-use nostr_types::Unixtime;
-use std::time::Duration;
+use crate::wait;
+use nostr_types::{Filter, Unixtime};
 
 pub async fn empty_tags() -> Result<Outcome, Error> {
     let (id, raw_event) =
@@ -14,7 +13,7 @@ pub async fn empty_tags() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_raw_event(id, raw_event, Duration::from_secs(WAIT))
+        .post_raw_event(id, raw_event, wait())
         .await?;
 
     if ok {
@@ -23,3 +22,44 @@ pub async fn empty_tags() -> Result<Outcome, Error> {
         Ok(Outcome::fail(Some(reason)))
     }
 }
+
+pub async fn kind_larger_than_16bit() -> Result<Outcome, Error> {
+    // A relay storing kinds in 16 bits would turn this into kind 34464
+    let (id, raw_event) =
+        Globals::make_raw_event(&format!("{}", Unixtime::now().0), "100000", "[]", "", true);
+
+    let (ok, reason) = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event(id, raw_event, wait())
+        .await?;
+
+    if !ok {
+        // An explicit rejection is fine, silently mangling the kind is not
+        return Ok(Outcome::pass(Some(format!("Rejected: {}", reason))));
+    }
+
+    let mut filter = Filter::new();
+    filter.ids = vec![id.into()];
+    let events = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events();
+
+    match events.first() {
+        None => Ok(Outcome::fail(Some(
+            "Accepted the event but did not serve it back".to_owned(),
+        ))),
+        Some(e) if Into::<u32>::into(e.kind) != 100000 => Ok(Outcome::fail(Some(format!(
+            "Served the event back as kind {}",
+            Into::<u32>::into(e.kind)
+        )))),
+        Some(_) => Ok(Outcome::pass(None)),
+    }
+}
@@ -5,12 +5,18 @@ pub mod ephemeral;
 pub mod filters;
 pub mod find;
 pub mod json;
+pub mod large;
 pub mod misc_events;
 pub mod nip11;
+pub mod protected;
 pub mod public;
+pub mod rate_limit;
 pub mod reg;
 pub mod replaceables;
 pub mod time;
+pub mod unicode;
+pub mod validation;
+pub mod vanish;
 
 use crate::error::Error;
 use crate::globals::{EventParts, GLOBALS};
@@ -20,7 +26,7 @@ use std::ops::Sub;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
-pub fn tbd() -> Result<Outcome, Error> {
+pub async fn tbd() -> Result<Outcome, Error> {
     Ok(Outcome::err("NOT YET IMPLEMENTED".to_string()))
 }
 
@@ -249,6 +255,37 @@ async fn maybe_submit_event_group_a_inner() -> Result<(), Error> {
     Ok(())
 }
 
+// After the relay drops the connection, get back to where Stage::Registered
+// left us so that later tests are not affected
+async fn reconnect_registered() -> Result<(), Error> {
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+    connection.reconnect().await?;
+    let _ = connection.wait_for_message(Duration::from_secs(1)).await?;
+    connection.authenticate_registered_if_challenged().await
+}
+
+// Drop the connection and start a new one which has not authenticated
+async fn fresh_connection() -> Result<(), Error> {
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+    connection.disconnect().await?;
+    connection.reconnect().await
+}
+
+// Put back the connection that Stage::Registered left us with, after a test
+// that used a fresh_connection()
+async fn restore_registered() -> Result<(), Error> {
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .disconnect()
+        .await?;
+    reconnect_registered().await
+}
+
 fn minutes_ago(m: u64) -> Unixtime {
     Unixtime::now().sub(Duration::new(m * 60, 0))
 }
@@ -0,0 +1,132 @@
+use super::{fresh_connection, restore_registered, tags};
+use crate::connection::AuthState;
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::EventKind;
+use std::time::Duration;
+
+// Each test starts over on a new connection so that it controls if and as
+// whom we authenticate, then puts the registered connection back.
+
+pub async fn rejected_without_auth() -> Result<Outcome, Error> {
+    let outcome = rejected_without_auth_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn rejected_without_auth_inner() -> Result<Outcome, Error> {
+    fresh_connection().await?;
+
+    // Let any challenge arrive, but do not answer it
+    let _ = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .wait_for_message(Duration::from_secs(1))
+        .await?;
+
+    let (ok, reason) = post_protected().await?;
+    if ok {
+        Ok(Outcome::fail(Some(
+            "Accepted a protected event without auth".to_owned(),
+        )))
+    } else if reason.starts_with("auth-required:") {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(format!(
+            "Rejected, but not with auth-required: {reason}"
+        ))))
+    }
+}
+
+pub async fn accepted_from_authed_author() -> Result<Outcome, Error> {
+    let outcome = accepted_from_authed_author_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn accepted_from_authed_author_inner() -> Result<Outcome, Error> {
+    fresh_connection().await?;
+
+    let state = authenticate(true).await?;
+    if state == AuthState::NotYetRequested {
+        // Some relays only challenge once a protected event shows up
+        let (ok, _) = post_protected().await?;
+        if ok {
+            return Ok(Outcome::pass(Some(
+                "Accepted without asking for auth".to_owned(),
+            )));
+        }
+        if authenticate(true).await? != AuthState::Success {
+            return Ok(Outcome::err("Could not authenticate".to_owned()));
+        }
+    } else if state != AuthState::Success {
+        return Ok(Outcome::err("Could not authenticate".to_owned()));
+    }
+
+    let (ok, reason) = post_protected().await?;
+    if ok {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(reason)))
+    }
+}
+
+pub async fn rejected_from_other_authed_key() -> Result<Outcome, Error> {
+    let outcome = rejected_from_other_authed_key_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn rejected_from_other_authed_key_inner() -> Result<Outcome, Error> {
+    fresh_connection().await?;
+
+    // Authenticate as the stranger but publish the registered user's event
+    if authenticate(false).await? != AuthState::Success {
+        return Ok(Outcome::err(
+            "Could not authenticate as the stranger".to_owned(),
+        ));
+    }
+
+    let (ok, reason) = post_protected().await?;
+    if ok {
+        Ok(Outcome::fail(Some(
+            "Accepted a protected event from another authenticated key".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(Some(reason)))
+    }
+}
+
+// A protected event (NIP-70) by the registered user
+async fn post_protected() -> Result<(bool, String), Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["-"]]),
+            "protected".to_string(),
+        ),
+        true,
+    )?;
+
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event, wait())
+        .await
+}
+
+async fn authenticate(registered: bool) -> Result<AuthState, Error> {
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .authenticate(registered, wait())
+        .await
+}
@@ -2,9 +2,8 @@ use super::tags;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::EventKind;
-use std::time::Duration;
 
 pub async fn public_can_write() -> Result<Outcome, Error> {
     let event = Globals::make_event(
@@ -17,7 +16,7 @@ pub async fn public_can_write() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -38,7 +37,7 @@ pub async fn accepts_relay_lists_from_public() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -59,7 +58,7 @@ pub async fn accepts_dm_relay_lists_from_public() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -84,7 +83,7 @@ pub async fn accepts_ephemeral_events_from_public() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -0,0 +1,347 @@
+use super::{reconnect_registered, restore_registered, tags};
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::{ClientMessage, EventKind, Filter, Id, RelayMessage, Signer};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+// Enough requests to trip a limit meant to stop floods
+const BURST: usize = 100;
+
+// How long we wait before checking whether the relay has lifted a limit,
+// and how many times
+const BACKOFF: Duration = Duration::from_secs(10);
+const BACKOFF_TRIES: usize = 3;
+
+// How relays word a NOTICE about rate limiting
+const RATE_LIMIT_WORDS: [&str; 6] = [
+    "rate-limit",
+    "rate limit",
+    "ratelimit",
+    "too many",
+    "too fast",
+    "slow down",
+];
+
+// What a burst of requests ran into
+#[derive(Debug, Default)]
+struct Throttling {
+    // How many requests went through before the first rate-limited: reply
+    first_rate_limited: Option<usize>,
+    rate_limited: usize,
+
+    // Refusals for any other reason
+    other_refusals: Vec<String>,
+
+    // NOTICEs during the burst that look like rate limiting
+    notices: Vec<String>,
+
+    // How many requests were answered before the relay dropped us
+    disconnected_after: Option<usize>,
+
+    unanswered: usize,
+}
+
+impl Throttling {
+    fn limited(&mut self, answered: usize) {
+        if self.first_rate_limited.is_none() {
+            self.first_rate_limited = Some(answered);
+        }
+        self.rate_limited += 1;
+    }
+
+    // Other NOTICEs have nothing to do with the burst
+    fn notice(&mut self, notice: String) {
+        let lowercase = notice.to_lowercase();
+        if RATE_LIMIT_WORDS.iter().any(|w| lowercase.contains(w)) {
+            self.notices.push(notice);
+        }
+    }
+
+    fn throttled(&self) -> bool {
+        self.first_rate_limited.is_some()
+            || self.disconnected_after.is_some()
+            || self.unanswered > 0
+            || !self.notices.is_empty()
+    }
+
+    // Graceful throttling tells the client what happened: a rate-limited:
+    // reply or a NOTICE. Abrupt throttling drops requests or the connection.
+    fn graceful(&self) -> bool {
+        self.disconnected_after.is_none() && self.unanswered == 0
+    }
+
+    fn outcome(&self, what: &str, recovered: Option<Duration>) -> Outcome {
+        let mut info: Vec<String> = Vec::new();
+        if let Some(n) = self.first_rate_limited {
+            info.push(format!(
+                "rate-limited: after {} {}, {} refused",
+                n, what, self.rate_limited
+            ));
+        }
+        if let Some(reason) = self.other_refusals.first() {
+            info.push(format!(
+                "{} otherwise refused, first: {}",
+                self.other_refusals.len(),
+                reason
+            ));
+        }
+        if let Some(notice) = self.notices.first() {
+            info.push(format!("{} NOTICEs, first: {}", self.notices.len(), notice));
+        }
+        if let Some(n) = self.disconnected_after {
+            info.push(format!("disconnected after {} {}", n, what));
+        }
+        if self.unanswered > 0 {
+            info.push(format!("{} {} never answered", self.unanswered, what));
+        }
+
+        if !self.throttled() {
+            info.push(format!("no throttling within {} {}", BURST, what));
+            return Outcome::pass(Some(info.join("; ")));
+        }
+
+        match recovered {
+            Some(d) => info.push(format!("recovered after {}s", d.as_secs())),
+            None => info.push("did not recover".to_owned()),
+        }
+
+        let info = info.join("; ");
+        if !self.graceful() {
+            Outcome::fail(Some(format!("abrupt: {info}")))
+        } else if recovered.is_none() {
+            Outcome::fail(Some(info))
+        } else {
+            Outcome::pass(Some(format!("graceful: {info}")))
+        }
+    }
+}
+
+pub async fn publishing() -> Result<Outcome, Error> {
+    let outcome = publishing_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn publishing_inner() -> Result<Outcome, Error> {
+    let throttling = publish_burst().await?;
+    let recovered = if throttling.throttled() {
+        recovery(publish_accepted).await?
+    } else {
+        None
+    };
+    Ok(throttling.outcome("events", recovered))
+}
+
+// Send all the events without waiting, then collect the OKs
+async fn publish_burst() -> Result<Throttling, Error> {
+    let mut throttling = Throttling::default();
+    let mut ids: Vec<Id> = Vec::new();
+
+    {
+        let mut lock = GLOBALS.connection.write();
+        let connection = lock.as_mut().unwrap();
+
+        for i in 0..BURST {
+            let event = Globals::make_event(
+                EventParts::Basic(
+                    EventKind::TextNote,
+                    tags(&[&["t", "rate-limit"]]),
+                    format!("rate limit test {i}"),
+                ),
+                true,
+            )?;
+            ids.push(event.id);
+            let message = ClientMessage::Event(Box::new(event));
+            if connection.send_message(message).await.is_err() {
+                break;
+            }
+        }
+
+        let mut answered: Vec<Id> = Vec::new();
+        while answered.len() < ids.len() {
+            match connection.wait_for_message(wait()).await {
+                Ok(None) => break,
+                Ok(Some(RelayMessage::Ok(id, ok, reason))) => {
+                    if !ids.contains(&id) || answered.contains(&id) {
+                        continue;
+                    }
+                    if !ok {
+                        if reason.starts_with("rate-limited:") {
+                            throttling.limited(answered.len());
+                        } else {
+                            throttling.other_refusals.push(reason);
+                        }
+                    }
+                    answered.push(id);
+                }
+                Ok(Some(RelayMessage::Notice(notice))) => throttling.notice(notice),
+                Ok(Some(_)) => {}
+                Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+                    throttling.disconnected_after = Some(answered.len());
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        throttling.unanswered = ids.len() - answered.len();
+    }
+
+    if throttling.disconnected_after.is_some() {
+        reconnect_registered().await?;
+    }
+
+    Ok(throttling)
+}
+
+async fn publish_accepted() -> Result<bool, Error> {
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["t", "rate-limit"]]),
+            "rate limit recovery".to_owned(),
+        ),
+        true,
+    )?;
+
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event, wait())
+        .await;
+
+    match result {
+        Ok((ok, _)) => Ok(ok),
+        Err(Error::TimedOut) => Ok(false),
+        Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+            reconnect_registered().await?;
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn subscriptions() -> Result<Outcome, Error> {
+    let outcome = subscriptions_inner().await;
+    restore_registered().await?;
+    outcome
+}
+
+async fn subscriptions_inner() -> Result<Outcome, Error> {
+    let throttling = subscription_burst().await?;
+    let recovered = if throttling.throttled() {
+        recovery(subscription_answered).await?
+    } else {
+        None
+    };
+    Ok(throttling.outcome("subscriptions", recovered))
+}
+
+// Open (and close) subscriptions one after another as fast as the relay
+// answers them
+async fn subscription_burst() -> Result<Throttling, Error> {
+    let mut throttling = Throttling::default();
+
+    {
+        let mut lock = GLOBALS.connection.write();
+        let connection = lock.as_mut().unwrap();
+
+        let mut answered: usize = 0;
+        'burst: for _ in 0..BURST {
+            let sub_id = connection.next_subscription_id();
+            let message = ClientMessage::Req(sub_id.clone(), vec![filter()]);
+            if connection.send_message(message).await.is_err() {
+                throttling.disconnected_after = Some(answered);
+                break;
+            }
+
+            loop {
+                match connection.wait_for_message(wait()).await {
+                    // Stop here: if the relay is dropping requests, each of
+                    // the rest would cost us a whole wait()
+                    Ok(None) => {
+                        throttling.unanswered += 1;
+                        break 'burst;
+                    }
+                    Ok(Some(RelayMessage::Eose(sub))) if sub == sub_id => {
+                        if connection.close_subscription(sub_id).await.is_err() {
+                            throttling.disconnected_after = Some(answered + 1);
+                            break 'burst;
+                        }
+                        break;
+                    }
+                    Ok(Some(RelayMessage::Closed(sub, reason))) if sub == sub_id => {
+                        if reason.starts_with("rate-limited:") {
+                            throttling.limited(answered);
+                        } else {
+                            throttling.other_refusals.push(reason);
+                        }
+                        break;
+                    }
+                    Ok(Some(RelayMessage::Notice(notice))) => throttling.notice(notice),
+                    Ok(Some(_)) => {}
+                    Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+                        throttling.disconnected_after = Some(answered);
+                        break 'burst;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            answered += 1;
+        }
+    }
+
+    if throttling.disconnected_after.is_some() {
+        reconnect_registered().await?;
+    }
+
+    Ok(throttling)
+}
+
+async fn subscription_answered() -> Result<bool, Error> {
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter()], wait())
+        .await;
+
+    match result {
+        Ok(fetch_result) => Ok(fetch_result.post_eose_events.is_some()),
+        Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+            reconnect_registered().await?;
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn filter() -> Filter {
+    let mut filter = Filter::new();
+    filter.authors = vec![GLOBALS.registered_user.read().public_key().into()];
+    filter.kinds = vec![EventKind::TextNote];
+    filter.limit = Some(1);
+    filter
+}
+
+// Back off, then see whether the relay serves us again. Returns how long
+// that took, or None if it never did.
+async fn recovery<F, Fut>(probe: F) -> Result<Option<Duration>, Error>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<bool, Error>>,
+{
+    let started = Instant::now();
+    for _ in 0..BACKOFF_TRIES {
+        tokio::time::sleep(BACKOFF).await;
+        if probe().await? {
+            return Ok(Some(started.elapsed()));
+        }
+    }
+    Ok(None)
+}
@@ -2,10 +2,9 @@ use super::tags;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{EventKind, Id, Signature, Signer};
 use std::sync::atomic::Ordering;
-use std::time::Duration;
 
 pub async fn sends_ok_after_event() -> Result<Outcome, Error> {
     Ok(match GLOBALS.saw_ok_after_event.load(Ordering::Relaxed) {
@@ -27,7 +26,7 @@ pub async fn verifies_signatures() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -55,7 +54,7 @@ pub async fn verifies_id_hashes() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -3,9 +3,8 @@ use super::maybe_submit_event_group_a; // tags
 use crate::error::Error;
 use crate::globals::GLOBALS; // EventParts, Globals
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{Event, EventKind, Filter, Id, Signer}; // PublicKeyHex;
-use std::time::Duration;
 
 pub async fn accepts_metadata() -> Result<Outcome, Error> {
     maybe_submit_event_group_a().await?;
@@ -35,7 +34,7 @@ pub async fn accepts_metadata() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -79,7 +78,7 @@ pub async fn replaces_metadata() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -126,7 +125,7 @@ pub async fn accepts_contact_list() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -170,7 +169,7 @@ pub async fn replaces_contact_list() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -212,7 +211,7 @@ pub async fn replaced_events_still_available_by_id() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -247,7 +246,7 @@ pub async fn replaceable_event_removes_previous() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -275,7 +274,7 @@ pub async fn replaceable_event_rejected_if_future() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -307,7 +306,7 @@ pub async fn addressable_event_removes_previous() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -335,7 +334,7 @@ pub async fn addressable_event_rejected_if_future() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -367,7 +366,7 @@ pub async fn find_replaceable_event() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -402,7 +401,7 @@ pub async fn find_addressable_event() -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .fetch_events(vec![filter], Duration::from_secs(WAIT))
+        .fetch_events(vec![filter], wait())
         .await?
         .into_events();
 
@@ -2,7 +2,7 @@ use super::tags;
 use crate::error::Error;
 use crate::globals::{EventParts, Globals, GLOBALS};
 use crate::outcome::Outcome;
-use crate::WAIT;
+use crate::wait;
 use nostr_types::{EventKind, Unixtime};
 use std::ops::{Add, Sub};
 use std::time::Duration;
@@ -76,7 +76,7 @@ async fn doit(u: Unixtime) -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_event(event, Duration::from_secs(WAIT))
+        .post_event(event, wait())
         .await?;
 
     if ok {
@@ -94,7 +94,7 @@ async fn doit_raw(date: &str) -> Result<Outcome, Error> {
         .write()
         .as_mut()
         .unwrap()
-        .post_raw_event(id, raw_event, Duration::from_secs(WAIT))
+        .post_raw_event(id, raw_event, wait())
         .await?;
 
     if ok {
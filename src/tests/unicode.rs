@@ -0,0 +1,256 @@
+use super::tags;
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::{Event, EventKind, Filter, Id, Signer, Unixtime};
+
+// A woman, a woman, a girl and a boy joined with zero-width joiners
+const ZWJ_FAMILY: &str = "\u{1F469}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+
+// Right-to-left mark, right-to-left override, pop directional formatting
+const RTL_MARKS: &str = "\u{200F}abc\u{202E}def\u{202C}";
+
+pub async fn surrogate_pair_escapes() -> Result<Outcome, Error> {
+    // U+1F600 written as an escaped UTF-16 surrogate pair. The id is computed
+    // over the canonical NIP-01 serialization, which has the character itself,
+    // so the escapes are only in what goes over the wire.
+    let escaped = r#"\ud83d\ude00"#;
+    let public_key_hex = GLOBALS.registered_user.read().public_key().as_hex_string();
+    let created_at = format!("{}", Unixtime::now().0);
+    let id = Globals::raw_event_id(
+        &public_key_hex,
+        &created_at,
+        "1",
+        "[[\"t\",\"\u{1F600}\"]]",
+        "\u{1F600}",
+    );
+    let signature = GLOBALS.registered_user.read().sign_id(id)?;
+    let raw_event = format!(
+        concat!(
+            r#"{{"id":"{}","pubkey":"{}","created_at":{},"kind":1,"#,
+            r#""tags":[["t","{}"]],"content":"{}","sig":"{}"}}"#
+        ),
+        id.as_hex_string(),
+        public_key_hex,
+        created_at,
+        escaped,
+        escaped,
+        signature.as_hex_string()
+    );
+
+    let (ok, reason) = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event(id, raw_event, wait())
+        .await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(reason)));
+    }
+
+    let events = fetch_by_tag('t', "\u{1F600}", EventKind::TextNote).await?;
+    match events.iter().find(|e| e.id == id) {
+        None => Ok(Outcome::fail(Some(
+            "Not found by the decoded tag value".to_owned(),
+        ))),
+        Some(e) if e.content != "\u{1F600}" => Ok(Outcome::fail(Some(format!(
+            "Content came back as {:?}",
+            e.content
+        )))),
+        Some(_) => Ok(Outcome::pass(None)),
+    }
+}
+
+pub async fn emoji_zwj_sequences() -> Result<Outcome, Error> {
+    roundtrip(ZWJ_FAMILY).await
+}
+
+pub async fn rtl_marks() -> Result<Outcome, Error> {
+    roundtrip(RTL_MARKS).await
+}
+
+pub async fn rejects_overlong_utf8() -> Result<Outcome, Error> {
+    // 0xC0 0xAF is an overlong encoding of '/', which is not valid UTF-8
+    let content: &[u8] = b"overlong\xc0\xafslash";
+
+    let public_key_hex = GLOBALS.registered_user.read().public_key().as_hex_string();
+    let created_at = Unixtime::now().0;
+
+    // The id is computed over the same invalid bytes, so the only thing wrong
+    // with this event is its encoding
+    let mut serial: Vec<u8> =
+        format!("[0,\"{}\",{},1,[],\"", public_key_hex, created_at).into_bytes();
+    serial.extend_from_slice(content);
+    serial.extend_from_slice(b"\"]");
+    use secp256k1::hashes::Hash;
+    let hash = secp256k1::hashes::sha256::Hash::hash(&serial);
+    let id = Id(hash.to_byte_array());
+    let signature = GLOBALS.registered_user.read().sign_id(id).unwrap();
+
+    let mut raw_event: Vec<u8> = format!(
+        "{{\"id\":\"{}\",\"pubkey\":\"{}\",\"created_at\":{},\"kind\":1,\"tags\":[],\"content\":\"",
+        id.as_hex_string(),
+        public_key_hex,
+        created_at
+    )
+    .into_bytes();
+    raw_event.extend_from_slice(content);
+    raw_event
+        .extend_from_slice(format!("\",\"sig\":\"{}\"}}", signature.as_hex_string()).as_bytes());
+
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event_bytes(id, &raw_event, wait())
+        .await;
+
+    match result {
+        Ok((true, _)) => Ok(Outcome::fail(Some("Accepted invalid UTF-8".to_owned()))),
+        Ok((false, reason)) => Ok(Outcome::pass(Some(reason))),
+        // Closing the websocket (status 1007) is the websocket-level rejection
+        Err(Error::Disconnected) | Err(Error::Websocket(_)) => {
+            super::reconnect_registered().await?;
+            Ok(Outcome::pass(Some("Closed the connection".to_owned())))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn lone_surrogate_escapes() -> Result<Outcome, Error> {
+    let (id, raw_event) = Globals::make_raw_event(
+        &format!("{}", Unixtime::now().0),
+        "1",
+        "[]",
+        r#"lone\ud800surrogate"#,
+        true,
+    );
+
+    let (ok, reason) = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event(id, raw_event, wait())
+        .await?;
+    if !ok {
+        return Ok(Outcome::pass(Some(format!("Rejected: {reason}"))));
+    }
+
+    // serde_json refuses lone surrogates, so look at the frame as text
+    match fetch_unparsed(id).await? {
+        None => Ok(Outcome::fail(Some(
+            "Accepted the event but did not serve it back".to_owned(),
+        ))),
+        Some(frame) if !frame.to_lowercase().contains(r#"lone\ud800surrogate"#) => Ok(
+            Outcome::fail(Some(format!("Served back altered content: {frame}"))),
+        ),
+        Some(_) => Ok(Outcome::pass(Some("Accepted and preserved".to_owned()))),
+    }
+}
+
+// Put the value in content, a t-tag and a d-tag, then find both events again
+// by tag value
+async fn roundtrip(value: &str) -> Result<Outcome, Error> {
+    let note = Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["t", value]]),
+            value.to_owned(),
+        ),
+        true,
+    )?;
+    let addressable = Globals::make_event(
+        EventParts::Basic(
+            EventKind::AppSpecificData,
+            tags(&[&["d", value]]),
+            value.to_owned(),
+        ),
+        true,
+    )?;
+
+    for event in [&note, &addressable] {
+        let (ok, reason) = GLOBALS
+            .connection
+            .write()
+            .as_mut()
+            .unwrap()
+            .post_event(event.clone(), wait())
+            .await?;
+        if !ok {
+            return Ok(Outcome::fail(Some(reason)));
+        }
+    }
+
+    let events = fetch_by_tag('t', value, EventKind::TextNote).await?;
+    match events.iter().find(|e| e.id == note.id) {
+        None => return Ok(Outcome::fail(Some("Not found by #t filter".to_owned()))),
+        Some(e) if *e != note => {
+            return Ok(Outcome::fail(Some(
+                "Served back an altered event".to_owned(),
+            )))
+        }
+        Some(_) => {}
+    }
+
+    let events = fetch_by_tag('d', value, EventKind::AppSpecificData).await?;
+    match events.iter().find(|e| e.id == addressable.id) {
+        None => Ok(Outcome::fail(Some("Not found by #d filter".to_owned()))),
+        Some(e) if *e != addressable => Ok(Outcome::fail(Some(
+            "Served back an altered event".to_owned(),
+        ))),
+        Some(_) => Ok(Outcome::pass(None)),
+    }
+}
+
+async fn fetch_by_tag(letter: char, value: &str, kind: EventKind) -> Result<Vec<Event>, Error> {
+    let filter = {
+        let mut filter = Filter::new();
+        filter.authors = vec![GLOBALS.registered_user.read().public_key().into()];
+        filter.kinds = vec![kind];
+        filter.add_tag_value(letter, value.to_owned());
+        filter
+    };
+
+    Ok(GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events())
+}
+
+// Fetch an event by id, returning the relay's EVENT frame without parsing it
+async fn fetch_unparsed(id: Id) -> Result<Option<String>, Error> {
+    let mut lock = GLOBALS.connection.write();
+    let connection = lock.as_mut().unwrap();
+
+    let sub_id = connection.next_subscription_id();
+    let wire = format!(
+        r#"["REQ","{}",{{"ids":["{}"]}}]"#,
+        sub_id.0,
+        id.as_hex_string()
+    );
+    connection.send_raw_message(wire).await?;
+
+    let id_hex = id.as_hex_string();
+    let quoted_sub = format!("\"{}\"", sub_id.0);
+    let mut frame: Option<String> = None;
+    while let Some(text) = connection.wait_for_text(wait()).await? {
+        if text.starts_with(r#"["EVENT""#) && text.contains(&id_hex) {
+            frame = Some(text);
+        } else if (text.starts_with(r#"["EOSE""#) || text.starts_with(r#"["CLOSED""#))
+            && text.contains(&quoted_sub)
+        {
+            break;
+        }
+    }
+    connection.close_subscription(sub_id).await?;
+
+    Ok(frame)
+}
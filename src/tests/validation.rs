@@ -0,0 +1,154 @@
+use crate::error::Error;
+use crate::globals::{Globals, GLOBALS, NIP01_FIELD_ORDER};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::{Id, Signer, Unixtime};
+use serde_json::Value;
+
+pub async fn uppercase_hex_id() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event("1", "[]");
+    let id_hex = id.as_hex_string();
+    let raw_event = raw_event.replacen(&id_hex, &id_hex.to_uppercase(), 1);
+    expect_invalid(id, raw_event, true).await
+}
+
+// The id is computed over the short pubkey too. No signature can verify
+// against a malformed pubkey, though, so a relay that only checks signatures
+// rejects this as well.
+pub async fn wrong_length_pubkey() -> Result<Outcome, Error> {
+    let pubkey_hex = GLOBALS.registered_user.read().public_key().as_hex_string();
+    let short_pubkey_hex = &pubkey_hex[..62];
+    let created_at = format!("{}", Unixtime::now().0);
+    let id = Globals::raw_event_id(short_pubkey_hex, &created_at, "1", "[]", CONTENT);
+    let sig = GLOBALS.registered_user.read().sign_id(id)?;
+    let raw_event = format!(
+        concat!(
+            r#"{{"id":"{}","pubkey":"{}","created_at":{},"kind":1,"tags":[],"#,
+            r#""content":"{}","sig":"{}"}}"#
+        ),
+        id.as_hex_string(),
+        short_pubkey_hex,
+        created_at,
+        CONTENT,
+        sig.as_hex_string()
+    );
+    expect_invalid(id, raw_event, true).await
+}
+
+// A signature of the wrong length cannot verify either, so this cannot tell a
+// length check from a signature check.
+pub async fn wrong_length_sig() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event("1", "[]");
+    let mut value: Value = serde_json::from_str(&raw_event)?;
+    let sig = value["sig"].as_str().unwrap_or_default().to_owned();
+    value["sig"] = Value::String(format!("{sig}00"));
+    expect_invalid(id, serde_json::to_string(&value)?, true).await
+}
+
+// Each required field is removed in turn. All must be rejected. There is no
+// id or signature that matches an event without one of its fields, so this
+// cannot tell a structure check from an id or signature check.
+pub async fn missing_fields() -> Result<Outcome, Error> {
+    for field in NIP01_FIELD_ORDER.iter() {
+        let (id, raw_event) = basic_raw_event("1", "[]");
+        let mut value: Value = serde_json::from_str(&raw_event)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove(*field);
+        }
+
+        // Without an id the relay has nothing to put in an OK message
+        let outcome = expect_invalid(id, serde_json::to_string(&value)?, *field != "id").await?;
+        if !outcome.pass.unwrap_or(false) {
+            return Ok(Outcome::fail(Some(format!(
+                "Missing \"{}\": {}",
+                field,
+                outcome.info.unwrap_or_default()
+            ))));
+        }
+    }
+
+    Ok(Outcome::pass(None))
+}
+
+// A second "kind" member after the first. Which one a parser keeps varies,
+// so the event is ambiguous and should be rejected.
+//
+// Extra fields with new names are not tested here: NIP-01 does not forbid
+// them, and PreservesNonstandardJsonFields expects a relay to keep them, so
+// rejecting them as invalid would contradict that test. A duplicate of a
+// standard field is the extra field that is unambiguously malformed.
+pub async fn duplicate_fields() -> Result<Outcome, Error> {
+    let (id, mut raw_event) = basic_raw_event("1", "[]");
+    raw_event.insert_str(raw_event.len() - 1, r#","kind":7"#);
+    expect_invalid(id, raw_event, true).await
+}
+
+pub async fn tags_not_arrays_of_strings() -> Result<Outcome, Error> {
+    for tags in [
+        r#"[["t",["nested"]]]"#,
+        r#"["t","flat"]"#,
+        r#"[{"t":"object"}]"#,
+    ] {
+        let (id, raw_event) = basic_raw_event("1", tags);
+        let outcome = expect_invalid(id, raw_event, true).await?;
+        if !outcome.pass.unwrap_or(false) {
+            return Ok(Outcome::fail(Some(format!(
+                "tags {}: {}",
+                tags,
+                outcome.info.unwrap_or_default()
+            ))));
+        }
+    }
+
+    Ok(Outcome::pass(None))
+}
+
+pub async fn numeric_tag_elements() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event("1", r#"[["t",1]]"#);
+    expect_invalid(id, raw_event, true).await
+}
+
+pub async fn kind_as_string() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event(r#""1""#, "[]");
+    expect_invalid(id, raw_event, true).await
+}
+
+pub async fn kind_as_float() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event("1.0", "[]");
+    expect_invalid(id, raw_event, true).await
+}
+
+pub async fn negative_kind() -> Result<Outcome, Error> {
+    let (id, raw_event) = basic_raw_event("-1", "[]");
+    expect_invalid(id, raw_event, true).await
+}
+
+const CONTENT: &str = "validation test";
+
+// The id and signature are computed over the malformed kind or tags, so the
+// only thing wrong with these events is their structure. Tests that break
+// the event afterwards say so.
+fn basic_raw_event(kind: &str, tags: &str) -> (Id, String) {
+    Globals::make_raw_event(&format!("{}", Unixtime::now().0), kind, tags, CONTENT, true)
+}
+
+async fn expect_invalid(id: Id, raw_event: String, ok_required: bool) -> Result<Outcome, Error> {
+    let result = GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_raw_event(id, raw_event, wait())
+        .await;
+
+    match result {
+        Ok((true, _)) => Ok(Outcome::fail(Some("Accepted".to_owned()))),
+        Ok((false, reason)) if reason.starts_with("invalid:") => Ok(Outcome::pass(Some(reason))),
+        Ok((false, reason)) => Ok(Outcome::fail(Some(format!(
+            "Rejected without the invalid: prefix: {reason}"
+        )))),
+        Err(Error::TimedOut) if !ok_required => Ok(Outcome::pass(None)),
+        Err(Error::TimedOut) => Ok(Outcome::fail(Some("No OK response".to_owned()))),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,223 @@
+use super::{maybe_submit_event_group_a, tags};
+use crate::error::Error;
+use crate::globals::{EventParts, Globals, GLOBALS};
+use crate::outcome::Outcome;
+use crate::wait;
+use nostr_types::{Event, EventKind, Filter, Id, KeySigner, PreEvent, Signer, Tag, Unixtime};
+use std::time::Duration;
+
+// These run at the end of the registered stage because they erase everything
+// the registered user has published.
+
+pub async fn removes_all_events() -> Result<Outcome, Error> {
+    maybe_submit_event_group_a().await?;
+    let group_ids: Vec<Id> = GLOBALS
+        .event_group_a
+        .read()
+        .iter()
+        .map(|(e, _)| e.id)
+        .collect();
+
+    let note = make_note(true)?;
+    let note_id = note.id;
+    let (ok, reason) = post(note).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let relay_url = GLOBALS.relay_url.read().clone();
+    let (ok, reason) = vanish(&relay_url).await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(reason)));
+    }
+
+    // Nothing by the registered user may remain except the request itself
+    let mut filter = Filter::new();
+    filter.authors = vec![GLOBALS.registered_user.read().public_key().into()];
+    let events = fetch(filter).await?;
+    let remaining: Vec<&Event> = events
+        .iter()
+        .filter(|e| e.kind != EventKind::from(62))
+        .collect();
+
+    if remaining.iter().any(|e| e.id == note_id) {
+        Ok(Outcome::fail(Some("A note survived".to_owned())))
+    } else if remaining.iter().any(|e| group_ids.contains(&e.id)) {
+        Ok(Outcome::fail(Some(
+            "An event group A event survived".to_owned(),
+        )))
+    } else if !remaining.is_empty() {
+        Ok(Outcome::fail(Some(format!(
+            "{} events survived",
+            remaining.len()
+        ))))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+pub async fn refuses_older_republication() -> Result<Outcome, Error> {
+    let note = make_note(true)?;
+    let note_id = note.id;
+    let (ok, reason) = post(note.clone()).await?;
+    if !ok {
+        return Ok(Outcome::err(reason));
+    }
+
+    let relay_url = GLOBALS.relay_url.read().clone();
+    let (ok, reason) = vanish(&relay_url).await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(reason)));
+    }
+
+    // Either refusing it or dropping it silently is fine
+    let _ = post(note).await?;
+
+    let mut filter = Filter::new();
+    filter.ids = vec![note_id.into()];
+    if fetch(filter).await?.is_empty() {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Served an event republished after vanishing".to_owned(),
+        )))
+    }
+}
+
+pub async fn leaves_others_alone() -> Result<Outcome, Error> {
+    let note = make_note(false)?;
+    let note_id = note.id;
+    let (ok, reason) = post(note).await?;
+    if !ok {
+        return Ok(Outcome::err(format!("Stranger could not post: {reason}")));
+    }
+
+    let relay_url = GLOBALS.relay_url.read().clone();
+    let (ok, reason) = vanish(&relay_url).await?;
+    if !ok {
+        return Ok(Outcome::fail(Some(reason)));
+    }
+
+    let mut filter = Filter::new();
+    filter.ids = vec![note_id.into()];
+    if fetch(filter).await?.is_empty() {
+        Ok(Outcome::fail(Some(
+            "Removed another author's event".to_owned(),
+        )))
+    } else {
+        Ok(Outcome::pass(None))
+    }
+}
+
+// An ALL_RELAYS request is meant to reach every relay, not just this one, so
+// it is made by a throwaway key rather than the registered user's
+pub async fn all_relays() -> Result<Outcome, Error> {
+    let throwaway = KeySigner::generate("vanish", 2)?;
+
+    let note = sign(
+        &throwaway,
+        EventKind::TextNote,
+        tags(&[&["test"]]),
+        "I will vanish",
+    )?;
+    let note_id = note.id;
+    let (ok, reason) = post(note).await?;
+    if !ok {
+        return Ok(Outcome::err(format!(
+            "Relay refused a note from a throwaway key: {reason}"
+        )));
+    }
+
+    // See vanish() for why we sleep
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let request = sign(
+        &throwaway,
+        EventKind::from(62),
+        tags(&[&["relay", "ALL_RELAYS"]]),
+        "vanish test",
+    )?;
+    let (ok, reason) = post(request).await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    if !ok {
+        return Ok(Outcome::fail(Some(reason)));
+    }
+
+    let mut filter = Filter::new();
+    filter.ids = vec![note_id.into()];
+    if fetch(filter).await?.is_empty() {
+        Ok(Outcome::pass(None))
+    } else {
+        Ok(Outcome::fail(Some(
+            "Note survived an ALL_RELAYS request".to_owned(),
+        )))
+    }
+}
+
+// Publish a NIP-62 request to vanish for the registered user, targeted at the
+// given relay url (or ALL_RELAYS).
+//
+// Relays erase events up to and including the request's created_at, so we keep
+// a second clear on either side of it. That way the events a test made just
+// before are covered, and the events the next test makes are not.
+async fn vanish(relay: &str) -> Result<(bool, String), Error> {
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let event = Globals::make_event(
+        EventParts::Basic(
+            EventKind::from(62),
+            tags(&[&["relay", relay]]),
+            "vanish test".to_string(),
+        ),
+        true,
+    )?;
+    let result = post(event).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    result
+}
+
+fn make_note(registered: bool) -> Result<Event, Error> {
+    Globals::make_event(
+        EventParts::Basic(
+            EventKind::TextNote,
+            tags(&[&["test"]]),
+            "I will vanish".to_string(),
+        ),
+        registered,
+    )
+}
+
+fn sign(
+    signer: &KeySigner,
+    kind: EventKind,
+    tags: Vec<Tag>,
+    content: &str,
+) -> Result<Event, Error> {
+    let pre_event = PreEvent {
+        pubkey: signer.public_key(),
+        created_at: Unixtime::now(),
+        kind,
+        tags,
+        content: content.to_owned(),
+    };
+    Ok(signer.sign_event(pre_event)?)
+}
+
+async fn post(event: Event) -> Result<(bool, String), Error> {
+    GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .post_event(event, wait())
+        .await
+}
+
+async fn fetch(filter: Filter) -> Result<Vec<Event>, Error> {
+    Ok(GLOBALS
+        .connection
+        .write()
+        .as_mut()
+        .unwrap()
+        .fetch_events(vec![filter], wait())
+        .await?
+        .into_events())
+}
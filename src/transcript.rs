@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// One websocket frame as it went over the wire
+#[derive(Debug, Clone)]
+pub struct Frame {
+    // Since the test started
+    pub at: Duration,
+
+    pub direction: Direction,
+
+    // Which of our connections carried it
+    pub connection: String,
+
+    pub text: String,
+}
+
+/// The frames of the test that is running. Nothing is recorded between
+/// tests.
+#[derive(Debug, Default)]
+pub struct Transcript {
+    started: Option<Instant>,
+    frames: Vec<Frame>,
+}
+
+impl Transcript {
+    pub fn start(&mut self) {
+        self.started = Some(Instant::now());
+        self.frames.clear();
+    }
+
+    pub fn finish(&mut self) -> Vec<Frame> {
+        self.started = None;
+        std::mem::take(&mut self.frames)
+    }
+
+    pub fn record(&mut self, connection: &str, direction: Direction, text: &str) {
+        if let Some(started) = self.started {
+            self.frames.push(Frame {
+                at: started.elapsed(),
+                direction,
+                connection: connection.to_owned(),
+                text: text.to_owned(),
+            });
+        }
+    }
+}